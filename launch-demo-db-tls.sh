@@ -0,0 +1,27 @@
+#!/usr/bin/env sh
+
+# Same as launch-demo-db.sh, but serves over TLS using a freshly generated
+# self-signed certificate. Connect with `wss://` or `https://` and trust
+# `certs/demo.crt` via `ClientBuilder::tls_root_cert`
+
+# cd to script directory
+cd "$(dirname "$0")"
+
+mkdir -p certs
+
+if [ ! -f certs/demo.crt ]; then
+    openssl req -x509 -newkey rsa:4096 -nodes -days 365 \
+        -keyout certs/demo.key \
+        -out certs/demo.crt \
+        -subj "/CN=localhost" \
+        -addext "subjectAltName=DNS:localhost,IP:127.0.0.1"
+fi
+
+surreal start file://sdb/example.db \
+    --user demo_user \
+    --pass demo_pass \
+    --bind 127.0.0.1:8000 \
+    --web-crt certs/demo.crt \
+    --web-key certs/demo.key \
+    --strict \
+    --log demo-db.log
@@ -76,14 +76,16 @@ pub fn query(input: TokenStreamOld) -> TokenStreamOld {
 
     let TailStatement { ref path, .. } = query_func.stmt;
     let run = query_func.stmt.get_runner();
+    let run = query_func.blocking.finish(client, quote!(
+        #client
+            . transaction()
+            #arg_steps
+            #push_steps
+            #run
+    ));
     let out = quote!(
         {
-            let result: ::sdb::prelude::SdbResult<#path> = #client
-                . transaction()
-                #arg_steps
-                #push_steps
-                #run
-                .await;
+            let result: ::sdb::prelude::SdbResult<#path> = #run;
 
             result
         }
@@ -162,20 +164,16 @@ pub fn queries(input: TokenStreamOld) -> TokenStreamOld {
     let (push_steps, unpack, result_act) = query_func.prepare(&trans);
     let arg_steps = query_func.arg_steps();
 
+    let run = query_func.blocking.finish(client, quote!{
+        #client . transaction()
+            #arg_steps
+            #push_steps
+            .run()
+    });
     let out = match unpack.is_empty() {
-        true => quote!{
-            #client . transaction()
-                #arg_steps
-                #push_steps
-                .run()
-                .await
-        },
+        true => run,
         false => quote!{    
-            let mut #trans = #client . transaction()
-                #arg_steps
-                #push_steps
-                .run()
-                .await #result_act;
+            let mut #trans = #run #result_act;
 
             #unpack
         },
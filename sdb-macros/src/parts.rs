@@ -1,4 +1,5 @@
 mod args;
+mod blocking;
 mod query;
 mod insert;
 mod result_type;
@@ -8,6 +9,7 @@ mod sugar;
 mod vars;
 
 pub(crate) use args::*;
+pub(crate) use blocking::*;
 pub(crate) use query::*;
 pub(crate) use insert::*;
 pub(crate) use result_type::*;
@@ -11,6 +11,8 @@ pub struct SdbArgs {
 }
 
 impl SdbArgs {
+    /// Binds each arg as `$0`, `$1`, ..., and by name when it has one. These are
+    /// sent as query vars, not `LET` statements, so they don't shift statement indices
     pub fn field_assigns(&self) -> TokenStream {
         let mut assigns = TokenStream::new();
         for (idx, arg) in self.fields.iter().enumerate() {
@@ -0,0 +1,29 @@
+use ::proc_macro2::TokenStream;
+use ::quote::quote;
+use ::syn::{parse::*, Ident, Token};
+
+syn::custom_keyword!(blocking);
+
+/// `blocking` before the client, for running on a `sdb::blocking::SurrealClient`
+/// instead of awaiting
+pub(crate) struct BlockingTok(pub Option<blocking>);
+
+impl Parse for BlockingTok {
+    fn parse(input: ParseStream) -> Result<Self> {
+        // the client itself could be called `blocking`
+        match input.peek(blocking) && (input.peek2(Ident) || input.peek2(Token![!])) {
+            true => Ok(Self(Some(input.parse()?))),
+            false => Ok(Self(None)),
+        }
+    }
+}
+
+impl BlockingTok {
+    /// Await `future`, or have `client` block on it
+    pub fn finish(&self, client: &Ident, future: TokenStream) -> TokenStream {
+        match self.0 {
+            Some(_) => quote!( #client . block_on( #future ) ),
+            None => quote!( #future .await ),
+        }
+    }
+}
@@ -2,9 +2,10 @@ use ::proc_macro2::TokenStream;
 use ::quote::quote;
 use ::syn::{parse::*, punctuated::Punctuated, token::*, *};
 
-use crate::parts::{SdbArgs, SdbStatement, ImportStatement};
+use crate::parts::{BlockingTok, SdbArgs, SdbStatement, ImportStatement};
 
 pub(crate) struct MultiQueryParse {
+    pub blocking: BlockingTok,
     pub async_tok: Option<Token!(!)>,
     pub client: Ident,
     pub args: Option<SdbArgs>,
@@ -17,6 +18,7 @@ impl Parse for MultiQueryParse {
     fn parse(input: ParseStream) -> Result<Self> {
         let context;
         Ok(Self {
+            blocking: input.parse()?,
             async_tok: input.parse()?,
             client: input.parse()?,
             args: input.parse()?,
@@ -2,10 +2,11 @@ use ::proc_macro2::TokenStream;
 use ::quote::quote;
 use ::syn::{parse::*, token::*, *};
 
-use crate::parts::{SdbArgs, TailStatement};
+use crate::parts::{BlockingTok, SdbArgs, TailStatement};
 
 
 pub(crate) struct SingleQueryParse {
+    pub blocking: BlockingTok,
     pub async_tok: Option<Token!(!)>,
     pub client: Ident,
     pub args: Option<SdbArgs>,
@@ -16,6 +17,7 @@ pub(crate) struct SingleQueryParse {
 impl Parse for SingleQueryParse {
     fn parse(input: ParseStream) -> Result<Self> {
         Ok(Self {
+            blocking: input.parse()?,
             async_tok: input.parse()?,
             client: input.parse()?,
             args: input.parse()?,
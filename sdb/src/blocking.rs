@@ -0,0 +1,112 @@
+//! A client for programs without an async runtime, like CLI tools and build
+//! scripts. Each method blocks the thread until the server replies.
+//!
+//! ```rust
+//! # use sdb::prelude::*;
+//! let client = sdb::blocking::SurrealClient::demo();
+//!
+//! let trans = client.transaction().push("SELECT * FROM books LIMIT 5");
+//! let mut reply = client.query(trans).unwrap();
+//! let books = reply.next_vec::<sdb::Value>().unwrap();
+//! ```
+//!
+//! The [`query!`](crate::query) and [`queries!`](crate::queries) macros run on
+//! a blocking client when it's prefixed with `blocking`:
+//!
+//! ```rust
+//! # use sdb::prelude::*;
+//! # fn main() -> SdbResult<()> {
+//! let client = sdb::blocking::SurrealClient::demo();
+//! let books = sdb::query!( blocking client => "SELECT * FROM books LIMIT 5" as Vec<sdb::Value> )?;
+//! # Ok( () )
+//! # }
+//! ```
+//!
+//! These methods panic if called from within an async runtime. Use the async
+//! [`SurrealClient`](crate::prelude::SurrealClient) there instead
+
+use ::std::{future::Future, sync::Arc};
+use ::tokio::runtime::{Builder, Runtime};
+
+use crate::prelude::{
+    ClientBuilder, QueryReply, RecordId, SdbError, SdbResult, SurrealClient as AsyncClient,
+    SurrealRecord, TransactionBuilder,
+};
+
+/// Like [`SurrealClient`](crate::prelude::SurrealClient), but blocking. Clones
+/// share a connection, and the runtime requests are run on
+#[derive(Clone)]
+pub struct SurrealClient {
+    client: AsyncClient,
+    runtime: Arc<Runtime>,
+}
+
+impl SurrealClient {
+    /// Run `client`'s requests on a runtime of their own
+    pub fn new(client: AsyncClient) -> SdbResult<Self> {
+        // a worker thread keeps websockets read between requests
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|err| SdbError::RuntimeError { reason: err.to_string() })?;
+
+        Ok(Self {
+            client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// See [`SurrealClient::demo`](fn@crate::prelude::SurrealClient::demo)
+    pub fn demo() -> Self {
+        Self::new(AsyncClient::demo()).unwrap()
+    }
+
+    /// The async client this wraps
+    pub fn as_async(&self) -> &AsyncClient {
+        &self.client
+    }
+
+    /// Block until `future` finishes. For running the async client's methods,
+    /// like [`select`](fn@crate::prelude::SurrealClient::select)
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// Create a new [`TransactionBuilder`], to run with [`query`](fn@SurrealClient::query)
+    pub fn transaction(&self) -> TransactionBuilder {
+        self.client.transaction()
+    }
+
+    /// Execute a Transaction and return the server's reply
+    pub fn query(&self, trans: TransactionBuilder) -> SdbResult<QueryReply> {
+        self.block_on(self.client.query(trans))
+    }
+
+    /// See [`SurrealClient::create`](fn@crate::prelude::SurrealClient::create)
+    pub fn create<R: SurrealRecord>(&self, record: R) -> SdbResult<RecordId> {
+        self.block_on(self.client.create(record))
+    }
+
+    /// See [`SurrealClient::insert`](fn@crate::prelude::SurrealClient::insert)
+    pub fn insert<R: SurrealRecord>(&self, records: Vec<R>) -> SdbResult<Vec<RecordId>> {
+        self.block_on(self.client.insert(records))
+    }
+
+    /// See [`SurrealClient::update`](fn@crate::prelude::SurrealClient::update)
+    pub fn update<R: SurrealRecord>(&self, record: &R) -> SdbResult<R> {
+        self.block_on(self.client.update(record))
+    }
+
+    /// See [`SurrealClient::change_db`](fn@crate::prelude::SurrealClient::change_db)
+    pub fn change_db(&self, new_db: &str) -> SdbResult<()> {
+        self.block_on(self.client.change_db(new_db))
+    }
+}
+
+impl ClientBuilder {
+    /// Build a [`blocking::SurrealClient`](SurrealClient)
+    pub fn build_blocking(self) -> SdbResult<SurrealClient> {
+        SurrealClient::new(self.build()?)
+    }
+}
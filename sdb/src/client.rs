@@ -1,13 +1,22 @@
-use ::std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
-use ::serde_json::{Map, Value};
+use ::std::sync::{Arc, RwLock, RwLockReadGuard};
+use ::serde::Serialize;
 
-use crate::prelude::*;
+use crate::{prelude::*, reply::Stopwatch, retry};
  
+mod auth;
 mod builder;
+mod config;
+mod crud;
 pub mod interface;
+#[cfg(feature = "ws")]
+mod live;
 
 pub use builder::*;
+#[allow(deprecated)]
+pub use crud::UpdateMode;
 pub use interface::*;
+#[cfg(feature = "ws")]
+pub use live::*;
 
 /// The URL to access the demo database. See [`demo()`](fn@SurrealClient::demo)
 const DEMO_URL: &str = "ws://demo_user:demo_pass@127.0.0.1:8000/example/demo";
@@ -37,13 +46,30 @@ impl SurrealClient {
         self.inner.server()
     }
 
+    /// Replace the connection settings. Interfaces with a session pick them up
+    /// on the next request
+    #[cfg(all(feature = "pool", not(target_family = "wasm")))]
+    pub(crate) fn set_server(&self, server: ServerInfo) {
+        self.inner.change_server(server);
+    }
+
+    /// Can the server still be reached with the current settings
+    #[cfg(all(feature = "pool", not(target_family = "wasm")))]
+    pub(crate) async fn is_healthy(&self) -> bool {
+        self.transaction()
+            .push("RETURN true")
+            .run()
+            .await
+            .is_ok()
+    }
+
     pub(crate) fn build(
         server: ServerInfo,
-        interface: impl SurrealInterface + 'static,
+        interface: Box<dyn SurrealInterface>,
     ) -> SdbResult<Self> {
  
         let inner = ClientInner {
-            socket: Box::new(Mutex::new( interface )),
+            interface,
             server: RwLock::new( server )
         };
 
@@ -58,41 +84,118 @@ impl SurrealClient {
     }
 
     /// Execute a Transaction and return the server's reply
-    pub async fn query(&mut self, trans: TransactionBuilder) -> SdbResult<QueryReply> {
-        let (queries, sqls) = trans.queries();
+    ///
+    /// Any number of queries can run at once on the same client, they don't wait
+    /// for each other to finish. Outside of wasm, they can run on other tasks too
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let task = tokio::spawn({
+    ///     let client = client.clone();
+    ///     async move { client.transaction().push("RETURN true").run().await }
+    /// });
+    /// task.await.unwrap()?;
+    /// # });
+    /// ```
+    pub async fn query(&self, trans: TransactionBuilder) -> SdbResult<QueryReply> {
+        if let Some( err ) = trans.var_error() {
+            return Err(err)
+        }
+
+        let policy = trans.retry.clone().unwrap_or_else(|| self.server().retry.clone());
+        // an atomic transaction which timed out or lost its connection might
+        // have committed, so only idempotent ones are re-run after that
+        let (atomic, idempotent) = (trans.atomic, trans.idempotent);
+        let retryable = |err: &SdbError| match (idempotent, atomic) {
+            (true, _) => err.is_retryable(),
+            (false, true) => err.was_not_applied(),
+            (false, false) => false,
+        };
+
+        let mut attempt = 1;
+        loop {
+            let result = self.query_once(trans.clone()).await;
+            let failed = match &result {
+                Ok( reply ) => retry::has_retryable_failure(reply, retryable),
+                Err( err ) => retryable(err),
+            };
+            if !failed || !policy.allows(attempt) {
+                return result
+            }
+
+            let delay = policy.delay(attempt);
+            #[cfg(feature = "log")]
+            log::warn!("Transaction attempt {attempt} failed, retrying in {delay:?}");
+            retry::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Run a transaction once, however it fails
+    async fn query_once(&self, trans: TransactionBuilder) -> SdbResult<QueryReply> {
+        let timeout = trans.timeout;
+        let atomic = trans.atomic;
+        let (queries, sqls, vars) = trans.queries();
         let full_sql = sqls.join(";\n\t");
+        let request_bytes = full_sql.len() + serde_json::to_vec(&vars).map_or(0, |json| json.len());
 
+        let request = SurrealRequest::query(&full_sql, vars).with_timeout(timeout);
+        let stopwatch = Stopwatch::start();
+        let response = self.run_request(request).await?;
+        let round_trip = stopwatch.elapsed();
         #[cfg(feature = "log")]
-        log::info!("Sending Query: \n\t{}\n", full_sql);
-        println!("Sending Query: \n\t{full_sql}\n");
+        self.warn_if_slow(&full_sql, round_trip);
 
-        let request = SurrealRequest::query(full_sql);
-        match self.run_request(request).await? {
-            SurrealResponse::Error { error, .. } => {
-                #[cfg(feature = "log")]
-                log::error!("SurrealDB response: {:?}", error);
-
-                panic!("Surreal Responded with an error\n{error:#?}\n");
-            }
+        match response {
+            SurrealResponse::Error { error, .. } => Err(SdbError::from_response(full_sql, error)),
             SurrealResponse::Result { result, .. } => match result {
-                Some(res) => Ok(QueryReply::new(queries, res)),
-                None => {
-                    println!("~ ~\n{result:?}\n");
-                    panic!()
+                Some(mut res) => {
+                    // some servers reply to BEGIN and COMMIT too
+                    if atomic && res.len() == queries.len() + 2 {
+                        res.pop();
+                        res.remove(0);
+                    }
+
+                    let mut reply = QueryReply::new(queries, res);
+                    reply.round_trip = round_trip;
+                    reply.request_bytes = request_bytes;
+                    if atomic {
+                        reply.check_atomic()?;
+                    }
+                    Ok(reply)
                 }
+                None => Err(SdbError::EmptyResponse { sql: full_sql }),
             },
+            SurrealResponse::Data { result, .. } => Err(SdbError::QueryResultParseFailure {
+                query: full_sql,
+                target_type: "Vec<StatementResult>".to_string(),
+                serde_err: serde::de::Error::custom("the reply isn't a list of statement results"),
+                value: Some(result),
+            }),
+        }
+    }
+
+    /// Log a warning if a transaction took longer than the client's
+    /// [slow query](crate::prelude::ClientBuilder::slow_query) threshold
+    #[cfg(feature = "log")]
+    fn warn_if_slow(&self, sql: &str, round_trip: std::time::Duration) {
+        if let Some( threshold ) = self.server().slow_query && round_trip > threshold {
+            log::warn!("Slow query took {round_trip:?}, over the {threshold:?} threshold:\n\t{sql}\n");
         }
     }
 
-    pub(crate) async fn run_request(&mut self, request: SurrealRequest) -> SdbResult<SurrealResponse> {
+    pub(crate) async fn run_request(&self, mut request: SurrealRequest) -> SdbResult<SurrealResponse> {
         let req_id = request.id;
-        let mut socket = self.inner.socket.lock().unwrap();
-        let response = socket.execute(&self.inner.server(), request).await?;
+        let server = self.server().clone();
+        if request.timeout.is_none() {
+            request.timeout = server.timeout;
+        }
+        let response = self.inner.interface.execute(&server, request).await?;
         if !response.check_id(req_id) {
-            unreachable!(
-                "Packets recieved out of order. {:?} {req_id:?}. Plz report to github",
-                response.id()
-            )
+            return Err(SdbError::MismatchedReply {
+                expected: req_id,
+                found: response.id(),
+            })
         }
         Ok( response )
     }
@@ -103,7 +206,7 @@ impl SurrealClient {
     /// perform the verification automatically, so using this is optional, but recomended
     /// 
     /// TODO: parse better
-    pub async fn handshake<'a>(&'a mut self) -> SdbResult<QueryReply> {
+    pub async fn handshake(&self) -> SdbResult<QueryReply> {
         // let mut lock = self.inner.socket.lock().unwrap();
         // let info = &self.server();
         // lock.ensure_connected(&info).await?;
@@ -115,21 +218,17 @@ impl SurrealClient {
 
     /// Change which database queries will act on and verifies that
     /// the server will accept this
-    pub async fn change_db(&mut self, new_db: &str) -> SdbResult<()>{
-        let ns = &self.server().namespace.clone();
-        self.change_ns(ns, new_db).await
+    pub async fn change_db(&self, new_db: &str) -> SdbResult<()>{
+        let ns = self.server().namespace.clone();
+        self.change_ns(&ns, new_db).await
     }
 
     /// Change which namespace and database queries will act on and verifies that
     /// the server will accept this
-    pub async fn change_ns(&mut self, new_ns: &str, new_db: &str) -> SdbResult<()> {
-        let req = SurrealRequest::use_ns_db( &self.server().namespace, new_db );
-        let response = self.run_request(req).await?;
-        match response {
-            SurrealResponse::Error { .. } => todo!(),
-            SurrealResponse::Result { .. } => {
-
-            },
+    pub async fn change_ns(&self, new_ns: &str, new_db: &str) -> SdbResult<()> {
+        let req = SurrealRequest::use_ns_db( new_ns, new_db );
+        if let SurrealResponse::Error { error, .. } = self.run_request(req).await? {
+            return Err(SdbError::from_response(format!("USE NS {new_ns} DB {new_db}"), error))
         }
 
         let mut new_server = self.server().clone();
@@ -140,89 +239,44 @@ impl SurrealClient {
     }
 
     /// Change which authentication credentials and verifies the server accepts them
-    pub async fn change_auth(&mut self, new_auth: Option<Credentials>) -> SdbResult<QueryReply> {
+    pub async fn change_auth(&self, new_auth: Option<Credentials>) -> SdbResult<QueryReply> {
         let mut new_server = self.server().clone();
         new_server.auth = new_auth;
+        new_server.token = None;
         self.inner.change_server(new_server);
         self.handshake().await
     }
 
-    // Attempts to retrieve a single record from the database by its id.
-    pub async fn fetch<R: SurrealRecord>(&mut self, _record_id: RecordId) -> SdbResult<Option<R>> {
-        todo!()
-    }
-
-    /// Update a single record, using the corresponding record struct.
-    pub async fn update<R>(&mut self, mode: UpdateMode, record: &R) -> SdbResult<QueryReply>
-    where
-        R: SurrealRecord
-    {
-        let fields = record.record_fields();
-        let contents = obj_to_contents( &fields );
-        let rid = record.id();
-
-        let sql = match mode {
-            UpdateMode::Content => format!("UPDATE {rid} CONTENT {contents} RETURN NONE"),
-            UpdateMode::Patch => format!("UPDATE {rid} PATCH {contents} RETURN NONE"),
-            UpdateMode::Merge => format!("UPDATE {rid} MERGE {contents} RETURN NONE"),
-        };
-
-        self.transaction()
-            .push(&sql)
-            .run()
-            .await
-    }
-
-    /// Create a new record. When creating the record in rust, set the `id` field
-    /// to `RecordId::placeholder()` to have the SurrealDb server generate the id for
-    /// you.
-    pub async fn create<R>(&mut self, record: R) -> SdbResult<RecordId>
-    where
-        R: SurrealRecord
-    {
-        let fields = record.record_fields();
-        let contents = obj_to_contents( &fields );
-        let rid = record.id();
-        let create = match rid.is_placeholder() {
-            true => rid.table(),
-            false => rid.to_string(),
-        };
-
-        let mut reply = self.transaction()
-            .push(&format!("CREATE {create} CONTENT {contents} RETURN id"))
-            .run()
-            .await?;
+    /// Set a connection-wide parameter, which every following query can use as `$name`.
+    ///
+    /// Websocket clients set it again automatically after reconnecting.
+    pub async fn set_param(&self, name: &str, value: impl Serialize) -> SdbResult<()> {
+        let value = serde_json::to_value(value).map_err(|serde_err| SdbError::InvalidVariable {
+            name: name.to_string(),
+            serde_err,
+        })?;
+
+        let req = SurrealRequest::let_param(name, value.clone());
+        if let SurrealResponse::Error { error, .. } = self.run_request(req).await? {
+            return Err(SdbError::from_response(format!("LET ${name} = {value}"), error))
+        }
 
-        reply.next_one::<RecordId>()
+        let mut new_server = self.server().clone();
+        new_server.params.insert(name.to_string(), value);
+        self.inner.change_server(new_server);
+        Ok( () )
     }
 
-
-    /// Insert a bunch of new records into the database, and return a list of their ids
-    pub async fn insert<R>(&mut self, records: Vec<R>) -> SdbResult<Vec<RecordId>>
-    where
-        R: SurrealRecord
-    {
-        let mut trans = self.transaction();
-
-        for rec in records {
-            let fields = rec.record_fields();
-            let contents = obj_to_contents( &fields );
-            let rid = rec.id();
-            let create = match rid.is_placeholder() {
-                true => rid.table(),
-                false => rid.to_string(),
-            };
-    
-            trans = trans.push(&format!("CREATE {create} CONTENT {contents} RETURN `id`"));
+    /// Clear a parameter set with [`set_param`](fn@SurrealClient::set_param)
+    pub async fn unset_param(&self, name: &str) -> SdbResult<()> {
+        if let SurrealResponse::Error { error, .. } = self.run_request(SurrealRequest::unset_param(name)).await? {
+            return Err(SdbError::from_response(format!("UNSET ${name}"), error))
         }
 
-        let results = trans.run().await?;
-
-        let mut ids = vec![];
-        for mut reply in results.replies {
-            ids.push( reply.parse_one::<RecordId>()? )
-        }
-        Ok(ids)
+        let mut new_server = self.server().clone();
+        new_server.params.remove(name);
+        self.inner.change_server(new_server);
+        Ok( () )
     }
 }
 
@@ -230,10 +284,8 @@ impl SurrealClient {
 //
 //
 
-unsafe impl Sync for ClientInner {}
-unsafe impl Send for ClientInner {}
 pub(crate) struct ClientInner {
-    socket: Box<Mutex<dyn SurrealInterface>>,
+    interface: Box<dyn SurrealInterface>,
     server: RwLock<ServerInfo>,
 }
 
@@ -248,26 +300,73 @@ impl ClientInner {
     }
 }
 
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "mock")]
+    use ::serde_json::Value;
 
-/// How records be altered by an update clause
-pub enum UpdateMode {
-    /// Replace the current record with these values
-    Content,
-    /// Change and append fields to an existing record, shallowly
-    Patch,
-    /// Change and append fields to an existing record, deaply
-    Merge,
-}
+    use super::*;
 
-fn obj_to_contents( fields: &Map<String, Value> ) -> String {
-    let val = fields.iter()
-        .filter_map(|(key, val)| {
-            if key.eq("id") { return None };
-            let s = serde_json::to_string(val).unwrap();
-            Some(format!("{key}: {s}"))
-        })
-        .collect::<Vec<String>>()
-        .join(", ");
+    /// Replies to every request as if it were the next one
+    struct WrongId;
+
+    #[async_trait::async_trait]
+    impl SurrealInterface for WrongId {
+        async fn execute(&self, _server: &ServerInfo, request: SurrealRequest) -> SdbResult<SurrealResponse> {
+            Ok(SurrealResponse::Result { id: request.id.wrapping_add(1), result: Some(vec![]) })
+        }
+    }
 
-    format!("{{{val}}}")
-}
\ No newline at end of file
+    #[test]
+    fn mismatched_reply_is_an_error() {
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(WrongId).unwrap();
+        let result = tokio_test::block_on(client.transaction().push("RETURN 1").run());
+        assert!(matches!(result, Err(SdbError::MismatchedReply { .. })));
+    }
+
+    #[cfg(feature = "mock")]
+    const ATOMIC_SQL: &str = "BEGIN TRANSACTION;\n\tCREATE books:1;\n\tCREATE books:2;\n\tCOMMIT TRANSACTION";
+
+    #[cfg(feature = "mock")]
+    fn run_atomic(replies: Vec<StatementResult>) -> SdbResult<QueryReply> {
+        let mock = MockInterface::new().expect(ATOMIC_SQL, replies);
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(mock).unwrap();
+        tokio_test::block_on(client.transaction()
+            .push("CREATE books:1")
+            .push("CREATE books:2")
+            .atomic()
+            .run())
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn atomic_strips_begin_and_commit() {
+        let mut reply = run_atomic(vec![
+            StatementResult::ok(Value::Null),
+            StatementResult::ok(1),
+            StatementResult::ok(2),
+            StatementResult::ok(Value::Null),
+        ]).unwrap();
+        assert_eq!(reply.next_one::<u32>().unwrap(), 1);
+        assert_eq!(reply.next_one::<u32>().unwrap(), 2);
+
+        // servers which don't reply to them
+        let mut reply = run_atomic(vec![StatementResult::ok(1), StatementResult::ok(2)]).unwrap();
+        assert_eq!(reply.next_one::<u32>().unwrap(), 1);
+        assert_eq!(reply.next_one::<u32>().unwrap(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn atomic_failure_names_the_cause() {
+        let result = run_atomic(vec![
+            StatementResult::ok(Value::Null),
+            StatementResult::err("The query was not executed due to a failed transaction"),
+            StatementResult::err("Database record `books:2` already exists"),
+            StatementResult::ok(Value::Null),
+        ]);
+        let Err( SdbError::TransactionFailed { index, detail, .. } ) = result else { panic!("expected TransactionFailed") };
+        assert_eq!(index, 1);
+        assert!(detail.contains("already exists"));
+    }
+}
@@ -0,0 +1,169 @@
+use ::serde_json::Value;
+
+use crate::prelude::*;
+
+impl SurrealClient {
+    /// Create a new user in a SCOPE, and sign in as them. Returns their JWT,
+    /// which every following request is authenticated with
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let creds = Credentials::scope("example", "demo", "reader")
+    ///     .param("email", "ann@example.com")
+    ///     .param("pass", "hunter2");
+    /// let token = client.signup(creds).await?;
+    /// # });
+    /// ```
+    pub async fn signup(&self, creds: Credentials) -> SdbResult<String> {
+        let response = self.run_request(SurrealRequest::signup(&creds)).await?;
+        let Some( token ) = token_from(response, "SIGNUP")? else {
+            return Err(SdbError::EmptyResponse { sql: "SIGNUP".to_string() })
+        };
+
+        self.use_session(Some(creds), Some(token.clone()));
+        Ok( token )
+    }
+
+    /// Sign in as a root, namespace, database or SCOPE user. Returns the JWT the
+    /// server replied with, if any, which every following request is
+    /// authenticated with
+    pub async fn signin(&self, creds: Credentials) -> SdbResult<Option<String>> {
+        let response = self.run_request(SurrealRequest::signin(&creds)).await?;
+        let token = token_from(response, "SIGNIN")?;
+
+        self.use_session(Some(creds), token.clone());
+        Ok( token )
+    }
+
+    /// Authenticate with a JWT from an earlier signin or signup
+    pub async fn authenticate(&self, token: impl ToString) -> SdbResult<()> {
+        let token = token.to_string();
+        let response = self.run_request(SurrealRequest::authenticate(&token)).await?;
+        token_from(response, "AUTHENTICATE")?;
+
+        let auth = self.server().auth.clone();
+        self.use_session(auth, Some(token));
+        Ok( () )
+    }
+
+    /// Forget the current credentials and token. Following requests are
+    /// unauthenticated
+    pub async fn invalidate(&self) -> SdbResult<()> {
+        let response = self.run_request(SurrealRequest::invalidate()).await?;
+        token_from(response, "INVALIDATE")?;
+
+        self.use_session(None, None);
+        Ok( () )
+    }
+
+    fn use_session(&self, auth: Option<Credentials>, token: Option<String>) {
+        let mut new_server = self.server().clone();
+        new_server.auth = auth;
+        new_server.token = token;
+        self.inner.change_server(new_server);
+    }
+}
+
+/// The token in a reply to an authentication request, if it has one
+fn token_from(response: SurrealResponse, what: &str) -> SdbResult<Option<String>> {
+    match response.into_data(what)? {
+        Value::String( token ) => Ok( Some( token ) ),
+        _ => Ok( None ),
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use super::*;
+
+    fn client(mock: &MockInterface) -> SurrealClient {
+        SurrealClient::open("127.0.0.1:8000/test/demo")
+            .build_with(mock.clone())
+            .unwrap()
+    }
+
+    fn root() -> Credentials {
+        Credentials::Basic { user: "root".to_string(), pass: "root".to_string() }
+    }
+
+    /// The credentials and token the client's requests are made with
+    fn session(client: &SurrealClient) -> (Option<Credentials>, Option<String>) {
+        let server = client.server();
+        (server.auth.clone(), server.token.clone())
+    }
+
+    #[test]
+    fn signin_keeps_the_credentials_and_token() {
+        let mock = MockInterface::new()
+            .expect(r#"SIGNIN {"pass":"root","user":"root"}"#, vec![StatementResult::ok("a.jwt")]);
+        let client = client(&mock);
+
+        let token = tokio_test::block_on(client.signin(root())).unwrap();
+
+        assert_eq!(token.as_deref(), Some("a.jwt"));
+        assert_eq!(session(&client), (Some(root()), Some("a.jwt".to_string())));
+        mock.assert_met();
+    }
+
+    #[test]
+    fn failed_signin_keeps_the_previous_session() {
+        let mock = MockInterface::new()
+            .expect(r#"SIGNIN {"pass":"root","user":"root"}"#, vec![StatementResult::ok("a.jwt")])
+            .expect(r#"SIGNIN {"pass":"wrong","user":"root"}"#, vec![StatementResult::err("There was a problem with authentication")]);
+        let client = client(&mock);
+
+        tokio_test::block_on(client.signin(root())).unwrap();
+        let wrong = Credentials::Basic { user: "root".to_string(), pass: "wrong".to_string() };
+        let Err( err ) = tokio_test::block_on(client.signin(wrong)) else { panic!("Signed in with the wrong password") };
+
+        assert!(err.is_auth());
+        assert_eq!(session(&client), (Some(root()), Some("a.jwt".to_string())));
+        mock.assert_met();
+    }
+
+    #[test]
+    fn signup_needs_a_token() {
+        let creds = Credentials::scope("test", "demo", "reader").param("email", "ann@example.com");
+        let sent = r#"SIGNUP {"DB":"demo","NS":"test","SC":"reader","email":"ann@example.com"}"#;
+        let mock = MockInterface::new()
+            .expect(sent, vec![StatementResult::ok(Value::Null)])
+            .expect(sent, vec![StatementResult::ok("b.jwt")]);
+        let client = client(&mock);
+
+        let result = tokio_test::block_on(client.signup(creds.clone()));
+        assert!(matches!(result, Err(SdbError::EmptyResponse { .. })));
+        assert_eq!(session(&client), (None, None));
+
+        let token = tokio_test::block_on(client.signup(creds.clone())).unwrap();
+        assert_eq!(token, "b.jwt");
+        assert_eq!(session(&client), (Some(creds), Some(token)));
+    }
+
+    #[test]
+    fn authenticate_replaces_the_token() {
+        let mock = MockInterface::new()
+            .expect(r#"SIGNIN {"pass":"root","user":"root"}"#, vec![StatementResult::ok("a.jwt")])
+            .expect(r#"AUTHENTICATE "b.jwt""#, vec![StatementResult::ok(Value::Null)]);
+        let client = client(&mock);
+
+        tokio_test::block_on(client.signin(root())).unwrap();
+        tokio_test::block_on(client.authenticate("b.jwt")).unwrap();
+
+        assert_eq!(session(&client), (Some(root()), Some("b.jwt".to_string())));
+        mock.assert_met();
+    }
+
+    #[test]
+    fn invalidate_forgets_the_session() {
+        let mock = MockInterface::new()
+            .expect(r#"SIGNIN {"pass":"root","user":"root"}"#, vec![StatementResult::ok("a.jwt")])
+            .expect("INVALIDATE", vec![StatementResult::ok(Value::Null)]);
+        let client = client(&mock);
+
+        tokio_test::block_on(client.signin(root())).unwrap();
+        tokio_test::block_on(client.invalidate()).unwrap();
+
+        assert_eq!(session(&client), (None, None));
+        mock.assert_met();
+    }
+}
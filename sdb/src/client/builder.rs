@@ -1,15 +1,28 @@
+use ::std::{sync::Arc, time::Duration};
+
 use crate::{
     client::SurrealClient, credentials::Credentials, error::SdbResult, interfaces, protocol::*,
-    server_info::ServerInfo,
+    reconnect::*, retry::RetryPolicy, server_info::ServerInfo, tls::TlsConfig,
 };
 
-use super::SurrealInterfaceBuilder;
+#[cfg(all(feature = "pool", not(target_family = "wasm")))]
+use crate::pool::{PoolConfig, SurrealPool};
+
+use super::{Layer, SurrealInterface, SurrealInterfaceBuilder};
 
 /// The info needed to build a [`SurrealClient`]
+#[derive(Clone)]
 pub struct ClientBuilder {
     connect_str: String,
     protocol: Option<Protocol>,
     auth: Option<Credentials>,
+    tls: TlsConfig,
+    reconnect: ReconnectPolicy,
+    listener: Option<ReconnectListener>,
+    timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    slow_query: Option<Duration>,
+    layers: Vec<Arc<dyn Layer>>,
 }
 
 impl ClientBuilder {
@@ -18,6 +31,13 @@ impl ClientBuilder {
             connect_str: connect_string.to_string(),
             protocol: None,
             auth: None,
+            tls: TlsConfig::default(),
+            reconnect: ReconnectPolicy::default(),
+            listener: None,
+            timeout: None,
+            retry: None,
+            slow_query: None,
+            layers: Vec::new(),
         }
     }
 
@@ -35,7 +55,7 @@ impl ClientBuilder {
         self
     }
 
-    /// Authenticate with username and password
+    /// Authenticate as a root user, with username and password
     pub fn auth_basic(mut self, user: impl ToString, pass: impl ToString) -> Self {
         self.auth = Some(Credentials::Basic {
             user: user.to_string(),
@@ -44,34 +64,224 @@ impl ClientBuilder {
         self
     }
 
-    // /// Not implemented
-    // pub fn auth_token(mut self, token: impl ToString) -> Self {
-    //     self.auth = Some(Credentials::Token {
-    //         token: token.to_string(),
-    //     });
-    //     self
-    // }
+    /// Authenticate as a user defined on the namespace `ns`
+    pub fn auth_namespace(mut self, ns: impl ToString, user: impl ToString, pass: impl ToString) -> Self {
+        self.auth = Some(Credentials::Namespace {
+            ns: ns.to_string(),
+            user: user.to_string(),
+            pass: pass.to_string(),
+        });
+        self
+    }
+
+    /// Authenticate as a user defined on the database `db`, in the namespace `ns`
+    pub fn auth_database(
+        mut self,
+        ns: impl ToString,
+        db: impl ToString,
+        user: impl ToString,
+        pass: impl ToString,
+    ) -> Self {
+        self.auth = Some(Credentials::Database {
+            ns: ns.to_string(),
+            db: db.to_string(),
+            user: user.to_string(),
+            pass: pass.to_string(),
+        });
+        self
+    }
+
+    /// Authenticate with a JWT, from an earlier signin or signup
+    pub fn auth_token(mut self, token: impl ToString) -> Self {
+        self.auth = Some(Credentials::Token {
+            token: token.to_string(),
+        });
+        self
+    }
+
+    /// Sign in as a SCOPE user. See [`Credentials::scope`]. Over HTTP, these have
+    /// to be exchanged for a token with [`signin`](SurrealClient::signin) first
+    pub fn auth_scope(mut self, creds: Credentials) -> Self {
+        self.auth = Some(creds);
+        self
+    }
+
+    /// Trust an extra PEM encoded root certificate when connecting over `wss://`
+    /// or `https://`. Useful for servers using a self-signed certificate.
+    pub fn tls_root_cert(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.root_certs.push(pem.into());
+        self
+    }
+
+    /// Present a client certificate when connecting over `wss://` or `https://`.
+    ///
+    /// `pkcs12` is a DER encoded PKCS #12 archive containing the certificate chain
+    /// and private key, and `password` is used to decrypt it.
+    pub fn tls_identity(mut self, pkcs12: impl Into<Vec<u8>>, password: impl ToString) -> Self {
+        self.tls.identity = Some((pkcs12.into(), password.to_string()));
+        self
+    }
+
+    /// Accept any certificate the server presents. **Only** use this for testing
+    /// against a local server.
+    pub fn tls_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.tls.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Set how a websocket client reconnects after its connection drops. Does
+    /// nothing for HTTP clients. See [`ReconnectPolicy`]
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Get told whenever a websocket client tries to reconnect, succeeds, or gives up
+    pub fn on_reconnect(mut self, listener: impl Fn(&ReconnectEvent) + Send + Sync + 'static) -> Self {
+        self.listener = Some(Arc::new(listener));
+        self
+    }
+
+    /// How long to wait for the server to reply to any request, before giving
+    /// up with [`SdbError::NetworkTimeout`](crate::prelude::SdbError::NetworkTimeout).
+    /// Transactions can override it with [`TransactionBuilder::timeout`](crate::prelude::TransactionBuilder::timeout)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// How to re-run atomic or idempotent transactions which conflict with others,
+    /// or lose their connection. Transactions can override it with
+    /// [`TransactionBuilder::retry`](crate::prelude::TransactionBuilder::retry)
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Log a warning with the SQL of any transaction which takes longer than
+    /// `threshold` to get a reply, to find the queries worth speeding up. Does
+    /// nothing without the `log` feature
+    ///
+    /// ```rust
+    /// # use sdb::prelude::*;
+    /// # use std::time::Duration;
+    /// let client = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo")
+    ///     .slow_query(Duration::from_millis(250))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn slow_query(mut self, threshold: Duration) -> Self {
+        self.slow_query = Some(threshold);
+        self
+    }
+
+    /// Wrap the client's interface in `layer`. The first layer added is the
+    /// outermost, so it sees each request first and its reply last
+    ///
+    /// ```rust
+    /// # use sdb::prelude::*;
+    /// let client = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo")
+    ///     .layer(TracingLayer)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn layer(mut self, layer: impl Layer + 'static) -> Self {
+        self.layers.push(Arc::new(layer));
+        self
+    }
 
     pub fn build(self) -> SdbResult<SurrealClient> {
-        let server = ServerInfo::new(self.connect_str, self.protocol, self.auth)?;
+        let server = self.server_info()?;
+        self.client_for(server)
+    }
 
-        let proto = server.protocol.clone();
+    /// Build a client which sends its requests through `interface`, instead of
+    /// the one for its protocol. Like a [`MockInterface`](crate::prelude::MockInterface)
+    pub fn build_with(self, interface: impl SurrealInterface + 'static) -> SdbResult<SurrealClient> {
+        let server = self.server_info()?;
+        self.client_with(server, Box::new(interface))
+    }
+
+    /// Build a [`SurrealPool`] of clients, each with their own connection
+    #[cfg(all(feature = "pool", not(target_family = "wasm")))]
+    pub fn build_pool(self, config: PoolConfig) -> SdbResult<SurrealPool> {
+        let server = self.server_info()?;
+        SurrealPool::new(self, server, config, None)
+    }
+
+    /// Build a [`SurrealPool`] whose clients send their requests through the
+    /// interfaces `interface` makes, instead of the one for their protocol. Like
+    /// clones of a [`MockInterface`](crate::prelude::MockInterface)
+    #[cfg(all(feature = "pool", not(target_family = "wasm")))]
+    pub fn build_pool_with<I: SurrealInterface + 'static>(
+        self,
+        config: PoolConfig,
+        interface: impl Fn() -> I + Send + Sync + 'static,
+    ) -> SdbResult<SurrealPool> {
+        let server = self.server_info()?;
+        let make = Box::new(move || Box::new(interface()) as Box<dyn SurrealInterface>);
+        SurrealPool::new(self, server, config, Some(make))
+    }
+
+    pub(crate) fn server_info(&self) -> SdbResult<ServerInfo> {
+        let mut server = ServerInfo::new(self.connect_str.clone(), self.protocol.clone(), self.auth.clone())?;
+        server.tls = self.tls.clone();
+        if self.timeout.is_some() {
+            server.timeout = self.timeout;
+        }
+        if let Some( retry ) = &self.retry {
+            server.retry = retry.clone();
+        }
+        if self.slow_query.is_some() {
+            server.slow_query = self.slow_query;
+        }
 
-        if proto.is_secure() {
-            unimplemented!("Secure connections")
+        #[cfg(all(not(feature = "tls"), not(target_family = "wasm")))]
+        if server.protocol.is_secure() {
+            return Err(crate::error::SdbError::TlsError {
+                reason: format!("Connecting to {} requires the `tls` feature", server.full_url()),
+            });
         }
 
+        Ok(server)
+    }
+
+    /// Build a client with its own interface, which connects to `server`
+    pub(crate) fn client_for(&self, server: ServerInfo) -> SdbResult<SurrealClient> {
+        let inter = self.interface_for(&server)?;
+        SurrealClient::build( server, self.wrap(inter) )
+    }
+
+    pub(crate) fn client_with(&self, server: ServerInfo, interface: Box<dyn SurrealInterface>) -> SdbResult<SurrealClient> {
+        SurrealClient::build(server, self.wrap(interface))
+    }
+
+    fn wrap(&self, interface: Box<dyn SurrealInterface>) -> Box<dyn SurrealInterface> {
+        self.layers.iter().rev().fold(interface, |inner, layer| layer.layer(inner))
+    }
+
+    /// Build just the interface a client would send its requests through, to
+    /// wrap in another like a [`RecordingInterface`](crate::prelude::RecordingInterface)
+    pub fn build_interface(self) -> SdbResult<Box<dyn SurrealInterface>> {
+        let server = self.server_info()?;
+        self.interface_for(&server)
+    }
+
+    fn interface_for(&self, server: &ServerInfo) -> SdbResult<Box<dyn SurrealInterface>> {
+        let proto = server.protocol.clone();
+
         match proto {
             #[cfg(feature = "ws")]
             Protocol::Socket { .. } => {
-                let inter = interfaces::WSSurrealInterface::new(&server)?;
-                SurrealClient::build( server, inter )
+                let inter = interfaces::WSSurrealInterface::new(server)?
+                    .with_reconnect(self.reconnect.clone(), self.listener.clone());
+                Ok( Box::new( inter ) )
             }
 
             #[cfg(feature = "http")]
             Protocol::Http { .. } => {
-                let inter = interfaces::HttpSurrealInterface::new(&server)?;
-                SurrealClient::build( server, inter )
+                let inter = interfaces::HttpSurrealInterface::new(server)?;
+                Ok( Box::new( inter ) )
             }
 
             #[cfg(feature = "tikv")]
@@ -84,3 +294,21 @@ impl ClientBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn secure_protocols_need_tls() {
+        for url in ["wss://127.0.0.1:8000/test/demo", "https://127.0.0.1:8000/test/demo"] {
+            let result = ClientBuilder::new(url).server_info();
+
+            #[cfg(feature = "tls")]
+            assert!(result.is_ok());
+            #[cfg(all(not(feature = "tls"), not(target_family = "wasm")))]
+            assert!(matches!(result, Err(crate::error::SdbError::TlsError { .. })));
+        }
+        assert!(ClientBuilder::new("ws://127.0.0.1:8000/test/demo").server_info().is_ok());
+    }
+}
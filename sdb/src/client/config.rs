@@ -0,0 +1,286 @@
+use ::std::env::{var, VarError};
+
+use crate::{prelude::*, server_info::url_encode};
+
+/// Environment variable naming the profile [`ClientBuilder::from_config`] uses
+#[cfg(feature = "config")]
+const PROFILE_VAR: &str = "SURREAL_PROFILE";
+
+impl ClientBuilder {
+    /// Configure a client from the same environment variables the compile-time
+    /// query checker uses, usually set in `.cargo/config.toml`:
+    ///
+    /// ```toml
+    /// [env]
+    /// SURREAL_HOST = "127.0.0.1:8000"
+    /// SURREAL_NS   = "example"
+    /// SURREAL_DB   = "demo"
+    /// SURREAL_USER = "test_user"
+    /// SURREAL_PASS = "test_pass"
+    /// ```
+    ///
+    /// Only `SURREAL_HOST` is required. The runtime client also accepts a protocol
+    /// and options in it, like `wss://127.0.0.1:8000?auth=db`. See [`ServerInfo::new`].
+    /// A host with a path, like `127.0.0.1:8000/example/demo`, already names the
+    /// namespace and database, so `SURREAL_NS` and `SURREAL_DB` are ignored. A
+    /// host with credentials, like `user:pass@127.0.0.1:8000`, can't be used
+    /// along with `SURREAL_USER`
+    pub fn from_env() -> SdbResult<Self> {
+        Self::from_lookup(|name| var(name))
+    }
+
+    /// [`from_env`](ClientBuilder::from_env), with variables read by `lookup`
+    fn from_lookup(lookup: impl Fn(&str) -> Result<String, VarError>) -> SdbResult<Self> {
+        let read = |name: &str| match lookup(name) {
+            Ok(val) if !val.is_empty() => Ok(Some(val)),
+            Ok(_) | Err(VarError::NotPresent) => Ok(None),
+            Err(VarError::NotUnicode(_)) => Err(SdbError::InvalidConfig {
+                origin: "environment".to_string(),
+                reason: format!("{name} is not valid unicode"),
+            }),
+        };
+
+        let Some( host ) = read("SURREAL_HOST")? else {
+            return Err(SdbError::InvalidConfig {
+                origin: "environment".to_string(),
+                reason: "SURREAL_HOST is not set".to_string(),
+            })
+        };
+
+        Self::from_parts(
+            "environment",
+            host,
+            read("SURREAL_NS")?,
+            read("SURREAL_DB")?,
+            read("SURREAL_USER")?,
+            read("SURREAL_PASS")?,
+        )
+    }
+
+    /// Configure a client from a profile in a TOML file. The profile is named
+    /// by the `SURREAL_PROFILE` environment variable, or is `default`
+    ///
+    /// ```toml
+    /// [default]
+    /// host = "127.0.0.1:8000"
+    /// ns   = "example"
+    /// db   = "demo"
+    /// user = "test_user"
+    /// pass = "test_pass"
+    ///
+    /// [production]
+    /// host = "wss://db.example.com/app/main?auth=db"
+    /// user = "app"
+    /// pass = "correct horse battery staple"
+    /// ```
+    ///
+    /// Profiles have the same fields as [`from_env`](ClientBuilder::from_env)'s
+    /// variables, and only `host` is required
+    #[cfg(feature = "config")]
+    pub fn from_config(path: impl AsRef<std::path::Path>) -> SdbResult<Self> {
+        let profile = var(PROFILE_VAR).unwrap_or_else(|_| "default".to_string());
+        Self::from_config_profile(path, &profile)
+    }
+
+    /// Like [`from_config`](ClientBuilder::from_config), but with a specific profile
+    #[cfg(feature = "config")]
+    pub fn from_config_profile(path: impl AsRef<std::path::Path>, profile: &str) -> SdbResult<Self> {
+        use ::std::collections::HashMap;
+
+        let path = path.as_ref();
+        let invalid = |reason: String| SdbError::InvalidConfig {
+            origin: path.display().to_string(),
+            reason,
+        };
+
+        let text = std::fs::read_to_string(path).map_err(|err| invalid(err.to_string()))?;
+        let mut profiles = toml::from_str::<HashMap<String, Profile>>(&text)
+            .map_err(|err| invalid(err.to_string()))?;
+
+        let Some( prof ) = profiles.remove(profile) else {
+            return Err(invalid(format!("There is no [{profile}] profile")))
+        };
+
+        Self::from_parts(&path.display().to_string(), prof.host, prof.ns, prof.db, prof.user, prof.pass)
+    }
+
+    fn from_parts(
+        origin: &str,
+        host: String,
+        ns: Option<String>,
+        db: Option<String>,
+        user: Option<String>,
+        pass: Option<String>,
+    ) -> SdbResult<Self> {
+        let connect_str = connect_string(&host, ns, db, user, pass).map_err(|reason| SdbError::InvalidConfig {
+            origin: origin.to_string(),
+            reason,
+        })?;
+        Ok(Self::new(&connect_str))
+    }
+}
+
+/// Puts the parts of a config together into a connection string. A host which
+/// already has a path keeps it, rather than having `ns` and `db` added on. Fails
+/// if the host has credentials, and so does the config
+fn connect_string(
+    host: &str,
+    ns: Option<String>,
+    db: Option<String>,
+    user: Option<String>,
+    pass: Option<String>,
+) -> Result<String, String> {
+    let (base, options) = match host.split_once('?') {
+        Some((base, options)) => (base, Some(options)),
+        None => (host, None),
+    };
+
+    let (proto, rest) = match base.split_once("://") {
+        Some((proto, rest)) => (format!("{proto}://"), rest),
+        None => (String::new(), base),
+    };
+
+    let authority = rest.split('/').next().unwrap_or_default();
+    if authority.contains('@') && (user.is_some() || pass.is_some()) {
+        return Err(format!("The host {host:?} already has credentials, so a user or password can't be set as well"));
+    }
+
+    // credentials go in the string, so its options still apply to them
+    let creds = match (user, pass) {
+        (Some(user), Some(pass)) => format!("{}:{}@", url_encode(&user), url_encode(&pass)),
+        (Some(user), None) => format!("{}@", url_encode(&user)),
+        _ => String::new(),
+    };
+
+    let rest = rest.trim_end_matches('/');
+    let mut connect_str = format!("{proto}{creds}{rest}");
+    if !rest.contains('/') {
+        for part in [ns, db].into_iter().flatten() {
+            connect_str.push('/');
+            connect_str.push_str(&url_encode(&part));
+        }
+    }
+    if let Some( options ) = options {
+        connect_str.push('?');
+        connect_str.push_str(options);
+    }
+
+    Ok(connect_str)
+}
+
+#[cfg(feature = "config")]
+#[derive(serde::Deserialize)]
+struct Profile {
+    host: String,
+    ns: Option<String>,
+    db: Option<String>,
+    user: Option<String>,
+    pass: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use ::std::collections::HashMap;
+
+    use super::*;
+
+    fn parts(host: &str) -> String {
+        connect_string(host, Some("test".to_string()), Some("demo".to_string()), None, None).unwrap()
+    }
+
+    /// A client configured from these environment variables
+    fn from_vars(vars: &[(&str, &str)]) -> SdbResult<ServerInfo> {
+        let vars = vars.iter()
+            .map(|(name, val)| (name.to_string(), val.to_string()))
+            .collect::<HashMap<_, _>>();
+        ClientBuilder::from_lookup(|name| vars.get(name).cloned().ok_or(VarError::NotPresent))?.server_info()
+    }
+
+    #[test]
+    fn adds_ns_and_db() {
+        assert_eq!(parts("127.0.0.1:8000"), "127.0.0.1:8000/test/demo");
+        assert_eq!(parts("ws://127.0.0.1:8000/?auth=db"), "ws://127.0.0.1:8000/test/demo?auth=db");
+        assert_eq!(
+            connect_string("127.0.0.1:8000", None, None, Some("user".to_string()), Some("pass".to_string())).unwrap(),
+            "user:pass@127.0.0.1:8000",
+        );
+    }
+
+    #[test]
+    fn keeps_the_hosts_path() {
+        assert_eq!(parts("127.0.0.1:8000/app/main"), "127.0.0.1:8000/app/main");
+        assert_eq!(parts("wss://db.example.com/app/main?auth=db"), "wss://db.example.com/app/main?auth=db");
+    }
+
+    #[test]
+    fn encodes_ns_and_db() {
+        let connect_str = connect_string("127.0.0.1:8000", Some("a/b".to_string()), Some("c?d".to_string()), None, None);
+        assert_eq!(connect_str.unwrap(), "127.0.0.1:8000/a%2Fb/c%3Fd");
+    }
+
+    #[test]
+    fn credentials_only_come_from_one_place() {
+        let user = Some("ann".to_string());
+        assert!(connect_string("root:root@127.0.0.1:8000", None, None, user.clone(), None).is_err());
+        assert!(connect_string("ws://root@127.0.0.1:8000/test/demo", None, None, None, Some("pw".to_string())).is_err());
+        assert!(connect_string("root:root@127.0.0.1:8000", None, None, None, None).is_ok());
+        assert!(connect_string("127.0.0.1:8000/me@example.com", None, None, user, None).is_ok());
+    }
+
+    #[test]
+    fn env_vars() {
+        let info = from_vars(&[
+            ("SURREAL_HOST", "127.0.0.1:8000"),
+            ("SURREAL_NS", "test"),
+            ("SURREAL_DB", "demo"),
+            ("SURREAL_USER", "ann"),
+            ("SURREAL_PASS", "p@ss"),
+        ]).unwrap();
+        assert_eq!((info.namespace.as_str(), info.database.as_str()), ("test", "demo"));
+        assert_eq!(info.auth, Some(Credentials::Basic { user: "ann".to_string(), pass: "p@ss".to_string() }));
+
+        // the host's path wins over SURREAL_NS and SURREAL_DB
+        let info = from_vars(&[("SURREAL_HOST", "127.0.0.1:8000/app/main"), ("SURREAL_NS", "test")]).unwrap();
+        assert_eq!((info.namespace.as_str(), info.database.as_str()), ("app", "main"));
+
+        let Err( SdbError::InvalidConfig { origin, .. } ) = from_vars(&[("SURREAL_NS", "test")]) else {
+            panic!("Configured without SURREAL_HOST")
+        };
+        assert_eq!(origin, "environment");
+
+        let both = from_vars(&[("SURREAL_HOST", "root:root@127.0.0.1:8000"), ("SURREAL_USER", "ann")]);
+        assert!(matches!(both, Err(SdbError::InvalidConfig { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "config")]
+    fn config_profiles() {
+        let path = std::env::temp_dir().join(format!("sdb-config-{}.toml", std::process::id()));
+        std::fs::write(&path, r#"
+            [default]
+            host = "127.0.0.1:8000"
+            ns = "test"
+            db = "demo"
+
+            [production]
+            host = "wss://db.example.com/app/main?auth=db"
+            user = "app"
+            pass = "secret"
+        "#).unwrap();
+
+        let default = ClientBuilder::from_config_profile(&path, "default").unwrap().server_info().unwrap();
+        assert_eq!((default.namespace.as_str(), default.database.as_str()), ("test", "demo"));
+        assert_eq!(default.auth, None);
+
+        let production = ClientBuilder::from_config_profile(&path, "production").unwrap().server_info().unwrap();
+        assert_eq!(production.hostname, "db.example.com");
+        assert!(matches!(production.auth, Some(Credentials::Database { .. })));
+
+        let missing = ClientBuilder::from_config_profile(&path, "staging");
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(missing, Err(SdbError::InvalidConfig { reason, .. }) if reason.contains("[staging]")));
+
+        let no_file = ClientBuilder::from_config_profile(&path, "default");
+        assert!(matches!(no_file, Err(SdbError::InvalidConfig { origin, .. }) if origin == path.display().to_string()));
+    }
+}
@@ -0,0 +1,268 @@
+use ::serde::{de::DeserializeOwned, Serialize};
+use ::serde_json::Value;
+
+use crate::prelude::*;
+
+impl SurrealClient {
+    /// Retrieve a single record by its id, or `None` if there isn't one
+    pub async fn fetch<R: SurrealRecord>(&self, record_id: RecordId) -> SdbResult<Option<R>> {
+        let records = self.select::<R>(record_id).await?;
+        Ok( records.into_iter().next() )
+    }
+
+    /// Retrieve every record in a table, or a single record by its id
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let books = client.select::<Book>("books").await?;
+    /// let one = client.select::<Book>(books[0].id.clone()).await?;
+    /// # });
+    /// ```
+    pub async fn select<R: SurrealRecord>(&self, what: impl Into<Resource>) -> SdbResult<Vec<R>> {
+        let what = what.into();
+        self.run_crud(SurrealRequest::select(&what), &what).await
+    }
+
+    /// Create a new record. When creating the record in rust, set the `id` field
+    /// to `RecordId::placeholder()` to have the SurrealDb server generate the id for
+    /// you.
+    pub async fn create<R>(&self, record: R) -> SdbResult<RecordId>
+    where
+        R: SurrealRecord
+    {
+        let what = record_resource(&record);
+        let data = contents(&record);
+        let ids = self.run_crud::<RecordId>(SurrealRequest::create(&what, data), &what).await?;
+
+        match ids.into_iter().next() {
+            Some( id ) => Ok( id ),
+            None => Err(SdbError::ZeroQueryResults { query: format!("CREATE {what}") }),
+        }
+    }
+
+    /// Replace a record's contents with those of `record`, and return the result
+    pub async fn update<R>(&self, record: &R) -> SdbResult<R>
+    where
+        R: SurrealRecord
+    {
+        let what = Resource::from(record.id());
+        let data = contents(record);
+        let updated = self.run_crud::<R>(SurrealRequest::update(&what, data), &what).await?;
+
+        match updated.into_iter().next() {
+            Some( rec ) => Ok( rec ),
+            None => Err(SdbError::ZeroQueryResults { query: format!("UPDATE {what}") }),
+        }
+    }
+
+    /// Update a single record, using the corresponding record struct
+    #[deprecated(note = "use `update` to replace a record, or `merge` to change some of its fields")]
+    #[allow(deprecated)]
+    pub async fn update_with<R>(&self, mode: UpdateMode, record: &R) -> SdbResult<R>
+    where
+        R: SurrealRecord
+    {
+        match mode {
+            UpdateMode::Content => self.update(record).await,
+            UpdateMode::Patch | UpdateMode::Merge => {
+                let what = Resource::from(record.id());
+                let updated = self.merge::<R>(what.clone(), contents(record)).await?;
+                match updated.into_iter().next() {
+                    Some( rec ) => Ok( rec ),
+                    None => Err(SdbError::ZeroQueryResults { query: format!("MERGE {what}") }),
+                }
+            },
+        }
+    }
+
+    /// Change some fields of a record, or every record in a table, leaving the
+    /// others as they are. Returns the changed records
+    pub async fn merge<R>(&self, what: impl Into<Resource>, data: impl Serialize) -> SdbResult<Vec<R>>
+    where
+        R: SurrealRecord
+    {
+        let what = what.into();
+        let data = serde_json::to_value(data).map_err(|serde_err| SdbError::SerializeFailure {
+            what: format!("MERGE {what}"),
+            serde_err,
+        })?;
+        self.run_crud(SurrealRequest::merge(&what, data), &what).await
+    }
+
+    /// Apply [JSON Patch](https://jsonpatch.com/) operations to a record, or every
+    /// record in a table. Returns the changed records
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let books = client.patch::<Book>("books:dune", vec![
+    ///     PatchOp::replace("/title", "Dune Messiah"),
+    ///     PatchOp::remove("/summary"),
+    /// ]).await?;
+    /// # });
+    /// ```
+    pub async fn patch<R>(&self, what: impl Into<Resource>, ops: Vec<PatchOp>) -> SdbResult<Vec<R>>
+    where
+        R: SurrealRecord
+    {
+        let what = what.into();
+        let ops = serde_json::to_value(ops).map_err(|serde_err| SdbError::SerializeFailure {
+            what: format!("PATCH {what}"),
+            serde_err,
+        })?;
+        self.run_crud(SurrealRequest::patch(&what, ops), &what).await
+    }
+
+    /// Delete a record, or every record in a table
+    pub async fn delete(&self, what: impl Into<Resource>) -> SdbResult<()> {
+        let what = what.into();
+        let response = self.run_request(SurrealRequest::delete(&what)).await?;
+        response.into_data(format!("DELETE {what}"))?;
+        Ok( () )
+    }
+
+    /// Insert a bunch of new records into the database, and return a list of their ids
+    pub async fn insert<R>(&self, records: Vec<R>) -> SdbResult<Vec<RecordId>>
+    where
+        R: SurrealRecord
+    {
+        let mut trans = self.transaction();
+
+        // the contents are sent as variables, so they never need escaping
+        for (idx, rec) in records.iter().enumerate() {
+            let var = format!("insert_{idx}");
+            trans = trans
+                .push_var(&var, contents(rec))
+                .push(&format!("CREATE {} CONTENT ${var} RETURN `id`", record_resource(rec)));
+        }
+
+        let results = trans.run().await?;
+
+        let mut ids = vec![];
+        for mut reply in results.replies {
+            ids.push( reply.parse_one::<RecordId>()? )
+        }
+        Ok(ids)
+    }
+
+    /// Send a `select`, `create`, etc. request, and parse the records it replies with
+    async fn run_crud<T>(&self, request: SurrealRequest, what: &Resource) -> SdbResult<Vec<T>>
+    where
+        T: DeserializeOwned
+    {
+        let desc = format!("{} {what}", format!("{:?}", request.method).to_uppercase());
+        let value = match self.run_request(request).await?.into_data(&desc)? {
+            Value::Null => Value::Array(vec![]),
+            Value::Array( arr ) => Value::Array( arr ),
+            other => Value::Array(vec![ other ]),
+        };
+
+        serde_json::from_value(value.clone()).map_err(|serde_err| SdbError::QueryResultParseFailure {
+            query: desc,
+            target_type: core::any::type_name::<T>().to_string(),
+            serde_err,
+            value: Some( value ),
+        })
+    }
+}
+
+/// How records be altered by [`update_with`](SurrealClient::update_with)
+#[deprecated(note = "use `SurrealClient::update`, `merge` or `patch` instead")]
+pub enum UpdateMode {
+    /// Replace the current record with these values
+    Content,
+    /// Change and append fields to an existing record. Now the same as `Merge`
+    Patch,
+    /// Change and append fields to an existing record, deeply
+    Merge,
+}
+
+/// Where to create a record: its table when the id is a placeholder
+fn record_resource<R: SurrealRecord>(record: &R) -> Resource {
+    let rid = record.id();
+    match rid.is_placeholder() {
+        true => Resource::Table( rid.table() ),
+        false => Resource::Record( rid.clone() ),
+    }
+}
+
+/// A record's fields, minus its `id`, which is part of the request instead
+fn contents<R: SurrealRecord>(record: &R) -> Value {
+    let mut fields = record.record_fields();
+    fields.remove("id");
+    Value::Object( fields )
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use ::serde::Deserialize;
+    use ::serde_json::json;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Book {
+        id: RecordId,
+        title: String,
+    }
+
+    impl SurrealRecord for Book {
+        fn id(&self) -> &RecordId {
+            &self.id
+        }
+
+        fn table_name(&self) -> String {
+            "books".to_string()
+        }
+    }
+
+    fn client(mock: &MockInterface) -> SurrealClient {
+        SurrealClient::open("127.0.0.1:8000/test/demo")
+            .build_with(mock.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn insert_sends_contents_as_variables() {
+        let mock = MockInterface::new().expect(
+            "CREATE books CONTENT $insert_0 RETURN `id`;\n\tCREATE books:`dune` CONTENT $insert_1 RETURN `id`",
+            vec![
+                StatementResult::ok(json!([{ "id": "books:new" }])),
+                StatementResult::ok(json!([{ "id": "books:dune" }])),
+            ],
+        );
+
+        let books = vec![
+            Book { id: RecordId::placeholder("books"), title: "Untitled".to_string() },
+            Book { id: RecordId::new("books", "dune"), title: "Dune".to_string() },
+        ];
+        let ids = tokio_test::block_on(client(&mock).insert(books)).unwrap();
+
+        assert_eq!(ids[1], RecordId::new("books", "dune"));
+        mock.assert_met();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn update_with_merges_fields() {
+        let mock = MockInterface::new()
+            .expect("MERGE books:`dune`", vec![StatementResult::ok(json!([{ "id": "books:dune", "title": "Dune" }]))]);
+
+        let book = Book { id: RecordId::new("books", "dune"), title: "Dune".to_string() };
+        let updated = tokio_test::block_on(client(&mock).update_with(UpdateMode::Merge, &book)).unwrap();
+
+        assert_eq!(updated.title, "Dune");
+        mock.assert_met();
+    }
+
+    #[test]
+    fn errors_keep_the_case_of_ids() {
+        let mock = MockInterface::new()
+            .expect("SELECT books:`Dune`", vec![StatementResult::ok(json!("not a book"))]);
+
+        let result = tokio_test::block_on(client(&mock).select::<Book>("books:Dune"));
+        let Err( SdbError::QueryResultParseFailure { query, .. } ) = result else {
+            panic!("Expected a parse failure, got {:?}", result.err())
+        };
+        assert_eq!(query, "SELECT books:`Dune`");
+    }
+}
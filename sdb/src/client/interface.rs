@@ -1,23 +1,42 @@
+mod layer;
 mod method;
 mod request;
 mod response;
 
+pub use layer::*;
 pub use method::*;
 pub use request::*;
 pub use response::*;
 
 use crate::{error::SdbResult, server_info::ServerInfo};
+#[cfg(feature = "ws")]
+use crate::{client::LiveReceiver, error::SdbError};
 
-#[async_trait::async_trait(?Send)]
-pub trait SurrealInterface: Send + Sync {
+/// `Send + Sync` everywhere but wasm, where nothing can move between threads
+/// and the browser's types aren't thread safe
+#[cfg(not(target_family = "wasm"))]
+pub trait MaybeSend: Send + Sync {}
+#[cfg(not(target_family = "wasm"))]
+impl<T: Send + Sync> MaybeSend for T {}
+
+#[cfg(target_family = "wasm")]
+pub trait MaybeSend {}
+#[cfg(target_family = "wasm")]
+impl<T> MaybeSend for T {}
+
+/// Sends requests to the server. The futures it returns are `Send` on native
+/// targets, so clients can be used from any task
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+pub trait SurrealInterface: MaybeSend {
     async fn execute(
-        &mut self,
+        &self,
         server: &ServerInfo,
         request: SurrealRequest,
     ) -> SdbResult<SurrealResponse>;
 
     async fn execute_list(
-        &mut self,
+        &self,
         server: &ServerInfo,
         requests: Vec<SurrealRequest>,
     ) -> Vec<SdbResult<SurrealResponse>> {
@@ -29,6 +48,28 @@ pub trait SurrealInterface: Send + Sync {
         }
         responses
     }
+
+    /// Start receiving the notifications of live query `live_id`. Only
+    /// interfaces with a persistent connection can do this
+    #[cfg(feature = "ws")]
+    fn subscribe(&self, _live_id: &str) -> SdbResult<LiveReceiver> {
+        Err(SdbError::Unsupported {
+            what: "Live queries".to_string(),
+        })
+    }
+
+    /// Stop receiving the notifications of live query `live_id`
+    #[cfg(feature = "ws")]
+    fn unsubscribe(&self, _live_id: &str) {}
+
+    /// Stop receiving the notifications of live query `live_id`, and kill it
+    /// on the server once possible. Used when a [`LiveQuery`](crate::prelude::LiveQuery)
+    /// is dropped where the kill can't be sent right away, like in `wasm`. The
+    /// websocket interfaces send it ahead of the next request
+    #[cfg(feature = "ws")]
+    fn kill_live(&self, live_id: &str) {
+        self.unsubscribe(live_id)
+    }
 }
 
 pub trait SurrealInterfaceBuilder: SurrealInterface {
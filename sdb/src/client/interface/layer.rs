@@ -0,0 +1,94 @@
+use super::{MaybeSend, SurrealInterface};
+#[cfg(feature = "log")]
+use super::{SurrealRequest, SurrealResponse};
+#[cfg(feature = "log")]
+use crate::{error::SdbResult, server_info::ServerInfo};
+#[cfg(all(feature = "log", feature = "ws"))]
+use crate::client::LiveReceiver;
+
+/// Wraps an interface in another, to change how requests are executed. Like
+/// logging, metrics, retrying, rewriting SQL or refreshing credentials. Added
+/// to a client with [`ClientBuilder::layer`](crate::prelude::ClientBuilder::layer)
+///
+/// The wrapping interface should pass `subscribe`, `unsubscribe` and `kill_live`
+/// on to the one it wraps, or live queries stop working through it.
+///
+/// Closures taking and returning a boxed interface are layers too
+pub trait Layer: MaybeSend {
+    fn layer(&self, inner: Box<dyn SurrealInterface>) -> Box<dyn SurrealInterface>;
+}
+
+impl<F> Layer for F
+where
+    F: Fn(Box<dyn SurrealInterface>) -> Box<dyn SurrealInterface> + MaybeSend,
+{
+    fn layer(&self, inner: Box<dyn SurrealInterface>) -> Box<dyn SurrealInterface> {
+        self(inner)
+    }
+}
+
+/// Logs every request, and its reply, with the `log` crate. The SQL sent is
+/// logged at `info`, replies at `debug`, and failures at `error`
+///
+/// ```rust
+/// # use sdb::prelude::*;
+/// let client = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo")
+///     .layer(TracingLayer)
+///     .build()
+///     .unwrap();
+/// ```
+#[cfg(feature = "log")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingLayer;
+
+#[cfg(feature = "log")]
+impl Layer for TracingLayer {
+    fn layer(&self, inner: Box<dyn SurrealInterface>) -> Box<dyn SurrealInterface> {
+        Box::new(Traced { inner })
+    }
+}
+
+#[cfg(feature = "log")]
+struct Traced {
+    inner: Box<dyn SurrealInterface>,
+}
+
+#[cfg(feature = "log")]
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+impl SurrealInterface for Traced {
+    async fn execute(
+        &self,
+        server: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        let id = request.id;
+        match request.sql() {
+            Some( sql ) => log::info!("Sending request {id}: \n\t{sql}\n"),
+            None => log::info!("Sending request {id}: {:?}", request.method),
+        }
+
+        let response = self.inner.execute(server, request).await;
+        match &response {
+            Ok( SurrealResponse::Error { error, .. } ) => log::error!("SurrealDB response to {id}: {error:?}"),
+            Ok( reply ) => log::debug!("SurrealDB response to {id}: {reply:?}"),
+            Err( err ) => log::error!("Request {id} failed: {err}"),
+        }
+        response
+    }
+
+    #[cfg(feature = "ws")]
+    fn subscribe(&self, live_id: &str) -> SdbResult<LiveReceiver> {
+        self.inner.subscribe(live_id)
+    }
+
+    #[cfg(feature = "ws")]
+    fn unsubscribe(&self, live_id: &str) {
+        self.inner.unsubscribe(live_id)
+    }
+
+    #[cfg(feature = "ws")]
+    fn kill_live(&self, live_id: &str) {
+        self.inner.kill_live(live_id)
+    }
+}
@@ -1,61 +1,163 @@
-use ::serde::Serialize;
+use ::std::time::Duration;
+use ::serde::{Deserialize, Serialize};
 use ::serde_json::{Map, Value};
 
-use crate::client::{RequestMethod, Credentials, ServerInfo};
+use crate::{
+    client::{RequestMethod, Credentials, ServerInfo},
+    record::Resource,
+};
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SurrealRequest {
     pub id: u32,
     pub method: RequestMethod,
     pub params: Vec<Value>,
+    /// How long to wait for the reply, before giving up with
+    /// [`NetworkTimeout`](crate::prelude::SdbError::NetworkTimeout)
+    #[serde(skip)]
+    pub timeout: Option<Duration>,
 }
 
-unsafe impl Send for SurrealRequest {}
-unsafe impl Sync for SurrealRequest {}
-
 impl SurrealRequest {
     fn new(method: RequestMethod, params: Vec<impl Into<Value>>) -> Self {
         Self {
             id: rand::random(),
             method,
             params: params.into_iter().map(|s| s.into()).collect::<Vec<Value>>(),
+            timeout: None,
         }
     }
 
-    pub fn query(sql: impl ToString) -> Self {
-        // println!("\n{}\n", sql.to_string());
-        Self::new(
-            RequestMethod::Query,
-            vec![sql.to_string()]
-        )
+    /// Give up waiting for the reply after `timeout`
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Run some SQL. `vars` are sent alongside it, and can be used in it as `$name`
+    pub fn query(sql: impl ToString, vars: Map<String, Value>) -> Self {
+        let mut params = vec![Value::String(sql.to_string())];
+        if !vars.is_empty() {
+            params.push(Value::Object(vars));
+        }
+        Self::new(RequestMethod::Query, params)
+    }
+
+    pub fn select(what: &Resource) -> Self {
+        Self::new(RequestMethod::Select, vec![what.to_string()])
+    }
+
+    pub fn create(what: &Resource, data: Value) -> Self {
+        Self::new(RequestMethod::Create, vec![Value::String(what.to_string()), data])
+    }
+
+    /// Replace the contents of records
+    pub fn update(what: &Resource, data: Value) -> Self {
+        Self::new(RequestMethod::Update, vec![Value::String(what.to_string()), data])
     }
 
+    /// Change some fields of records, leaving the rest as they are
+    pub fn merge(what: &Resource, data: Value) -> Self {
+        Self::new(RequestMethod::Merge, vec![Value::String(what.to_string()), data])
+    }
+
+    /// Apply JSON Patch operations to records
+    pub fn patch(what: &Resource, ops: Value) -> Self {
+        Self::new(RequestMethod::Patch, vec![Value::String(what.to_string()), ops])
+    }
+
+    pub fn delete(what: &Resource) -> Self {
+        Self::new(RequestMethod::Delete, vec![what.to_string()])
+    }
+
+    /// Stop a live query
+    pub fn kill(live_id: &str) -> Self {
+        Self::new(RequestMethod::Kill, vec![live_id.to_string()])
+    }
+
+    /// Pick the namespace and database. An empty name leaves it unchanged
     pub fn use_ns_db(ns: &str, db: &str) -> Self {
+        let name = |name: &str| match name.is_empty() {
+            true => Value::Null,
+            false => Value::String(name.to_string()),
+        };
         Self::new(
             RequestMethod::Use,
-            vec![ns.to_string(), db.to_string()]
+            vec![name(ns), name(db)]
         )
     }
 
-    pub fn stringify(&self) -> String {
-        serde_json::to_string(self).unwrap()
+    pub fn let_param(name: &str, value: Value) -> Self {
+        Self::new(
+            RequestMethod::Let,
+            vec![Value::String(name.to_string()), value]
+        )
     }
 
-    pub fn new_auth(info: &ServerInfo) -> Self {
-        let mut vals = Map::new();
+    pub fn unset_param(name: &str) -> Self {
+        Self::new(
+            RequestMethod::Unset,
+            vec![name.to_string()]
+        )
+    }
 
-        match &info.auth {
-            Some(Credentials::Basic { user, pass }) => {
-                vals.insert("user".to_string(), Value::String(user.clone()));
-                vals.insert("pass".to_string(), Value::String(pass.clone()));
-            }
+    pub fn signin(creds: &Credentials) -> Self {
+        Self::new(RequestMethod::Signin, vec![creds.signin_params()])
+    }
+
+    /// Create a new user in a SCOPE. `creds` must be [`Credentials::Scope`]
+    pub fn signup(creds: &Credentials) -> Self {
+        Self::new(RequestMethod::Signup, vec![creds.signin_params()])
+    }
+
+    pub fn authenticate(token: &str) -> Self {
+        Self::new(RequestMethod::Authenticate, vec![token.to_string()])
+    }
+
+    pub fn invalidate() -> Self {
+        Self::new(RequestMethod::Invalidate, Vec::<Value>::new())
+    }
+
+    /// The requests sent whenever a socket connects, or the connection
+    /// settings change: sign in (if there are credentials), pick the
+    /// namespace and database, then set any connection-wide params
+    pub fn handshake(info: &ServerInfo) -> Vec<Self> {
+        let mut reqs = Vec::new();
+        match info.session_auth() {
+            Some(Credentials::Token { token }) => reqs.push(Self::authenticate(&token)),
+            Some(creds) => reqs.push(Self::signin(&creds)),
             None => {}
-            _ => unimplemented!(),
         }
+        if !info.namespace.is_empty() {
+            reqs.push(Self::use_ns_db(&info.namespace, &info.database));
+        }
+        for (name, value) in &info.params {
+            reqs.push(Self::let_param(name, value.clone()));
+        }
+        reqs
+    }
 
-        Self::new(
-            RequestMethod::Signin,
-            vec![Value::Object(vals)]
-        )
+    pub fn stringify(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// The SQL this request runs. For `select`, `create` and the like, it's the
+    /// verb and what it acts on, like `SELECT books:1`. Requests which only
+    /// change the session, like `use`, have none
+    pub fn sql(&self) -> Option<String> {
+        let first = self.params.first().and_then(Value::as_str).unwrap_or_default();
+        match self.method {
+            RequestMethod::Query => Some(first.to_string()),
+            RequestMethod::Select
+            | RequestMethod::Create
+            | RequestMethod::Update
+            | RequestMethod::Merge
+            | RequestMethod::Patch
+            | RequestMethod::Delete => {
+                let verb = format!("{:?}", self.method).to_uppercase();
+                Some(format!("{verb} {first}"))
+            }
+            _ => None,
+        }
     }
 }
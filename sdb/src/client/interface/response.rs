@@ -1,17 +1,19 @@
-use ::serde::Deserialize;
+use ::serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use ::serde_json::Value;
 
 use crate::{
     reply::StatementResult,
     client::interface::SurrealRequest,
+    error::SdbError,
 };
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SurrealResponseError {
     pub code: isize,
     pub message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize)]
 #[serde(untagged)]
 pub enum SurrealResponse {
     Error {
@@ -22,10 +24,50 @@ pub enum SurrealResponse {
         id: u32,
         result: Option<Vec<StatementResult>>,
     },
+    /// The reply to a request other than `query`, like `select` or `create`,
+    /// which is just the records
+    Data {
+        id: u32,
+        result: Value,
+    },
 }
 
-unsafe impl Send for SurrealResponse {}
-unsafe impl Sync for SurrealResponse {}
+impl<'de> Deserialize<'de> for SurrealResponse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Raw {
+            id: u32,
+            #[serde(default)]
+            error: Option<SurrealResponseError>,
+            #[serde(default)]
+            result: Value,
+        }
+
+        let Raw { id, error, result } = Raw::deserialize(deserializer)?;
+        if let Some( error ) = error {
+            return Ok(SurrealResponse::Error { id, error })
+        }
+        if !is_statements(&result) {
+            return Ok(SurrealResponse::Data { id, result })
+        }
+
+        // statement results which don't parse are an error, not records
+        let result = serde_json::from_value(result).map_err(D::Error::custom)?;
+        Ok(SurrealResponse::Result { id, result })
+    }
+}
+
+/// Does a reply's `result` look like the results of a `query`. Statement
+/// results all have a `status`, and records always have an `id`
+fn is_statements(result: &Value) -> bool {
+    match result {
+        Value::Null => true,
+        Value::Array(items) => items.iter().all(|item| {
+            item.get("status").is_some() && item.get("id").is_none()
+        }),
+        _ => false,
+    }
+}
 
 type SurrealResponseResult = Result<Option<Vec<StatementResult>>, SurrealResponseError>;
 
@@ -33,6 +75,7 @@ impl From<SurrealResponse> for SurrealResponseResult {
     fn from(val: SurrealResponse) -> SurrealResponseResult {
         match val {
             SurrealResponse::Result { result, .. } => Ok(result),
+            SurrealResponse::Data { .. } => Ok(None),
             SurrealResponse::Error { error, .. } => Err(error),
         }
     }
@@ -42,6 +85,7 @@ impl SurrealResponse {
     pub fn id(&self) -> u32 {
         match self {
             SurrealResponse::Result { id, .. } => *id,
+            SurrealResponse::Data { id, .. } => *id,
             SurrealResponse::Error { id, .. } => *id,
         }
     }
@@ -49,15 +93,86 @@ impl SurrealResponse {
     pub fn check_id(&self, compare: u32) -> bool {
         match self {
             SurrealResponse::Result { id, .. } => *id == compare,
+            SurrealResponse::Data { id, .. } => *id == compare,
             SurrealResponse::Error { id, .. } => *id == compare,
         }
     }
 
+    /// The same reply, to the request with id `new_id`
+    #[cfg(feature = "mock")]
+    pub(crate) fn with_id(mut self, new_id: u32) -> Self {
+        match &mut self {
+            SurrealResponse::Result { id, .. }
+            | SurrealResponse::Data { id, .. }
+            | SurrealResponse::Error { id, .. } => *id = new_id,
+        }
+        self
+    }
+
     pub fn is_for(&self, request: &SurrealRequest) -> bool {
         let req_id = request.id;
         match self {
             SurrealResponse::Result { id, .. } => *id == req_id,
+            SurrealResponse::Data { id, .. } => *id == req_id,
             SurrealResponse::Error { id, .. } => *id == req_id,
         }
     }
+
+    /// The records in the reply to a `select`, `create`, `update`, `merge`,
+    /// `patch` or `delete` request
+    pub fn into_data(self, what: impl ToString) -> Result<Value, SdbError> {
+        match self {
+            SurrealResponse::Data { result, .. } => Ok(result),
+            SurrealResponse::Result { result: None, .. } => Ok(Value::Null),
+            // an empty list of records looks just like an empty list of statements
+            SurrealResponse::Result { result: Some(stmts), .. } if stmts.is_empty() => {
+                Ok(Value::Array(vec![]))
+            }
+            SurrealResponse::Result { .. } => Err(SdbError::EmptyResponse {
+                sql: what.to_string(),
+            }),
+            SurrealResponse::Error { error, .. } => Err(SdbError::from_response(what, error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(json: &str) -> serde_json::Result<SurrealResponse> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn statement_results() {
+        let reply = parse(r#"{"id":1,"result":[{"time":"1ms","status":"OK","result":[1,2]}]}"#).unwrap();
+        let SurrealResponse::Result { id: 1, result: Some( stmts ) } = reply else { panic!("{reply:?}") };
+        assert_eq!(stmts[0].result, serde_json::json!([1, 2]));
+
+        let reply = parse(r#"{"id":2,"result":null}"#).unwrap();
+        assert!(matches!(reply, SurrealResponse::Result { id: 2, result: None }));
+    }
+
+    #[test]
+    fn records_and_other_data() {
+        let reply = parse(r#"{"id":1,"result":[{"id":"books:1","status":"read"}]}"#).unwrap();
+        assert!(matches!(reply, SurrealResponse::Data { id: 1, .. }));
+
+        let reply = parse(r#"{"id":2,"result":"a-jwt-token"}"#).unwrap();
+        assert!(matches!(reply, SurrealResponse::Data { id: 2, .. }));
+    }
+
+    #[test]
+    fn errors() {
+        let reply = parse(r#"{"id":1,"error":{"code":-32000,"message":"There was a problem"}}"#).unwrap();
+        let SurrealResponse::Error { id: 1, error } = reply else { panic!("{reply:?}") };
+        assert_eq!(error.message, "There was a problem");
+    }
+
+    #[test]
+    fn broken_statement_results_fail() {
+        // neither a result nor a detail
+        assert!(parse(r#"{"id":1,"result":[{"time":"1ms","status":"OK"}]}"#).is_err());
+    }
 }
@@ -0,0 +1,297 @@
+use ::std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use ::futures::{channel::mpsc, Stream, StreamExt};
+use ::serde::{de::DeserializeOwned, Deserialize};
+use ::serde_json::Value;
+
+use crate::prelude::*;
+
+/// Where an interface delivers the raw notifications of a live query
+pub type LiveReceiver = mpsc::UnboundedReceiver<SdbResult<Value>>;
+
+/// A change to a record matched by a [`LiveQuery`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum LiveEvent<R> {
+    Create(R),
+    Update(R),
+    Delete(RecordId),
+}
+
+/// A live query's notifications, as an async [`Stream`]. Created with
+/// [`SurrealClient::live`].
+///
+/// Dropping it kills the live query. Outside of `wasm`, when dropped inside a
+/// tokio runtime, the kill is sent right away from a spawned task. Otherwise it
+/// is sent along with the client's next request, and a client which sends
+/// nothing else leaves it running until the connection closes. Use
+/// [`kill`](LiveQuery::kill) to stop it, and know that it stopped, either way.
+pub struct LiveQuery<R> {
+    id: String,
+    sql: String,
+    client: SurrealClient,
+    receiver: LiveReceiver,
+    _record: PhantomData<fn() -> R>,
+}
+
+impl SurrealClient {
+    /// Start a live query, and get notified whenever the records it selects change.
+    /// Only works over websockets
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// use futures::StreamExt;
+    ///
+    /// let mut books = client.live::<Book>("SELECT * FROM books WHERE word_count > 10000").await?;
+    /// while let Some( event ) = books.next().await {
+    ///     match event? {
+    ///         LiveEvent::Create( book ) => println!("New book: {}", book.title),
+    ///         LiveEvent::Update( book ) => println!("Changed book: {}", book.title),
+    ///         LiveEvent::Delete( id ) => println!("Deleted book: {id}"),
+    ///     }
+    /// #   break;
+    /// }
+    /// # });
+    /// ```
+    pub async fn live<R: DeserializeOwned>(&self, sql: &str) -> SdbResult<LiveQuery<R>> {
+        let sql = format!("LIVE {sql}");
+        let mut reply = self.transaction().push(&sql).run().await?;
+        let id = reply.next_one::<String>()?;
+        let receiver = match self.inner.interface.subscribe(&id) {
+            Ok( receiver ) => receiver,
+            Err( err ) => {
+                // nothing can receive its notifications, so don't leave it running
+                let _ = self.transaction().push(&format!("KILL \"{id}\"")).run().await;
+                return Err(err);
+            }
+        };
+
+        Ok(LiveQuery {
+            id,
+            sql,
+            client: self.clone(),
+            receiver,
+            _record: PhantomData,
+        })
+    }
+}
+
+impl<R> LiveQuery<R> {
+    /// The id the server gave this live query
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Stop the live query now, rather than when it's dropped
+    pub async fn kill(mut self) -> SdbResult<()> {
+        let id = std::mem::take(&mut self.id);
+        self.client.inner.interface.unsubscribe(&id);
+
+        let sql = format!("KILL \"{id}\"");
+        match self.client.run_request(SurrealRequest::kill(&id)).await? {
+            SurrealResponse::Error { error, .. } => Err(SdbError::from_response(sql, error)),
+            _ => Ok( () ),
+        }
+    }
+}
+
+impl<R: DeserializeOwned> LiveQuery<R> {
+    fn parse(&self, note: Value) -> SdbResult<LiveEvent<R>> {
+        let parse_err = |serde_err, target_type: &str, value: Value| SdbError::QueryResultParseFailure {
+            query: self.sql.clone(),
+            target_type: target_type.to_string(),
+            serde_err,
+            value: Some( value ),
+        };
+
+        let note = serde_json::from_value::<Notification>(note.clone())
+            .map_err(|err| parse_err(err, "Notification", note))?;
+
+        match note.action {
+            LiveAction::Create => serde_json::from_value(note.result.clone())
+                .map(LiveEvent::Create)
+                .map_err(|err| parse_err(err, core::any::type_name::<R>(), note.result)),
+            LiveAction::Update => serde_json::from_value(note.result.clone())
+                .map(LiveEvent::Update)
+                .map_err(|err| parse_err(err, core::any::type_name::<R>(), note.result)),
+            LiveAction::Delete => {
+                // older servers send just the id, newer ones the deleted record
+                let id = match &note.result {
+                    Value::Object( rec ) => rec.get("id").cloned().unwrap_or_default(),
+                    other => other.clone(),
+                };
+                serde_json::from_value(id)
+                    .map(LiveEvent::Delete)
+                    .map_err(|err| parse_err(err, "RecordId", note.result))
+            }
+        }
+    }
+}
+
+impl<R: DeserializeOwned> Stream for LiveQuery<R> {
+    type Item = SdbResult<LiveEvent<R>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.receiver.poll_next_unpin(cx) {
+            Poll::Ready(Some(Ok(note))) => Poll::Ready(Some(self.parse(note))),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(err))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<R> Drop for LiveQuery<R> {
+    fn drop(&mut self) {
+        // already killed
+        if self.id.is_empty() {
+            return;
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        if let Ok( runtime ) = tokio::runtime::Handle::try_current() {
+            let id = std::mem::take(&mut self.id);
+            let client = self.client.clone();
+            client.inner.interface.unsubscribe(&id);
+            runtime.spawn(async move {
+                let _ = client.run_request(SurrealRequest::kill(&id)).await;
+            });
+            return;
+        }
+
+        // wasm, or no runtime to send it from, so the interface sends it later
+        self.client.inner.interface.kill_live(&self.id);
+    }
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    action: LiveAction,
+    result: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum LiveAction {
+    Create,
+    Update,
+    Delete,
+}
+
+#[cfg(test)]
+mod test {
+    use ::std::sync::{Arc, Mutex};
+    use ::serde::Serialize;
+    use ::serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq, Serialize)]
+    struct Book {
+        id: RecordId,
+        title: String,
+    }
+
+    /// Remembers the live queries it was asked to kill, now or later
+    #[derive(Clone, Default)]
+    struct Kills {
+        sent: Arc<Mutex<Vec<String>>>,
+        deferred: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+    #[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+    impl SurrealInterface for Kills {
+        async fn execute(&self, _server: &ServerInfo, request: SurrealRequest) -> SdbResult<SurrealResponse> {
+            if let RequestMethod::Kill = request.method {
+                let id = request.params.first().and_then(Value::as_str).unwrap_or_default();
+                self.sent.lock().unwrap().push(id.to_string());
+            }
+            Ok( SurrealResponse::Data { id: request.id, result: Value::Null } )
+        }
+
+        fn kill_live(&self, live_id: &str) {
+            self.deferred.lock().unwrap().push(live_id.to_string());
+        }
+    }
+
+    fn live_query(interface: impl SurrealInterface + 'static) -> LiveQuery<Book> {
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(interface).unwrap();
+        LiveQuery {
+            id: "a-live-id".to_string(),
+            sql: "LIVE SELECT * FROM books".to_string(),
+            client,
+            receiver: mpsc::unbounded().1,
+            _record: PhantomData,
+        }
+    }
+
+    #[test]
+    fn drop_kills_from_the_runtime() {
+        let kills = Kills::default();
+        tokio_test::block_on(async {
+            drop(live_query(kills.clone()));
+            tokio::task::yield_now().await;
+        });
+        assert_eq!(*kills.sent.lock().unwrap(), vec!["a-live-id"]);
+        assert!(kills.deferred.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn drop_without_a_runtime_defers_the_kill() {
+        let kills = Kills::default();
+        drop(live_query(kills.clone()));
+        assert!(kills.sent.lock().unwrap().is_empty());
+        assert_eq!(*kills.deferred.lock().unwrap(), vec!["a-live-id"]);
+    }
+
+    #[test]
+    fn parses_notifications() {
+        let live = live_query(Kills::default());
+        let book = Book { id: RecordId::new("books", "dune"), title: "Dune".to_string() };
+        let record = json!({ "id": "books:dune", "title": "Dune" });
+
+        let created = live.parse(json!({ "action": "CREATE", "result": record })).unwrap();
+        assert_eq!(created, LiveEvent::Create(book));
+
+        let updated = live.parse(json!({ "action": "UPDATE", "result": { "id": "books:dune", "title": "Dune Messiah" } })).unwrap();
+        assert!(matches!(updated, LiveEvent::Update(Book { title, .. }) if title == "Dune Messiah"));
+
+        let by_id = live.parse(json!({ "action": "DELETE", "result": "books:dune" })).unwrap();
+        let by_record = live.parse(json!({ "action": "DELETE", "result": record })).unwrap();
+        assert_eq!(by_id, LiveEvent::Delete(RecordId::new("books", "dune")));
+        assert_eq!(by_record, by_id);
+    }
+
+    #[test]
+    fn rejects_malformed_notifications() {
+        let live = live_query(Kills::default());
+        let malformed = [
+            json!("books:dune"),
+            json!({ "result": { "id": "books:dune", "title": "Dune" } }),
+            json!({ "action": "RENAME", "result": "books:dune" }),
+            json!({ "action": "CREATE", "result": { "title": 3 } }),
+            json!({ "action": "DELETE", "result": { "title": "Dune" } }),
+        ];
+
+        for note in malformed {
+            let result = live.parse(note.clone());
+            assert!(matches!(result, Err(SdbError::QueryResultParseFailure { .. })), "{note}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn unsupported_live_query_is_killed() {
+        let mock = MockInterface::new()
+            .expect("LIVE SELECT * FROM books", vec![StatementResult::ok("a-live-id")])
+            .expect("KILL \"a-live-id\"", vec![StatementResult::ok(Value::Null)]);
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(mock.clone()).unwrap();
+
+        let result = tokio_test::block_on(client.live::<Value>("SELECT * FROM books"));
+        assert!(matches!(result, Err(SdbError::Unsupported { .. })));
+        mock.assert_met();
+    }
+}
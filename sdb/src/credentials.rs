@@ -1,32 +1,145 @@
 use base64::encode;
+use serde_json::{Map, Value};
 use std::fmt::Debug;
 
 /// The information required to log into the SurrealDB instance.
 ///
-/// TODO: implement Token and Digest methods
+/// TODO: implement Digest method
 #[derive(Clone, Debug, PartialEq)]
 pub enum Credentials {
     User { user: String },
+    /// A root user
     Basic { user: String, pass: String },
-    // Token { token: String },
+    /// A user defined with `DEFINE USER ... ON NAMESPACE`
+    Namespace { ns: String, user: String, pass: String },
+    /// A user defined with `DEFINE USER ... ON DATABASE`
+    Database { ns: String, db: String, user: String, pass: String },
+    /// A JWT, from an earlier signin or signup
+    Token { token: String },
+    /// A user of a SCOPE, identified by whatever `params` the scope's
+    /// `SIGNIN` and `SIGNUP` clauses use, like `email` and `pass`
+    Scope {
+        namespace: String,
+        database: String,
+        scope: String,
+        params: Map<String, Value>,
+    },
     // Digest {},
 }
 
 impl Credentials {
+    /// Credentials for a user of a SCOPE. Add the values it's signed in with
+    /// using [`param`](Credentials::param)
+    ///
+    /// ```rust
+    /// # use sdb::prelude::*;
+    /// let creds = Credentials::scope("example", "demo", "reader")
+    ///     .param("email", "ann@example.com")
+    ///     .param("pass", "hunter2");
+    /// ```
+    pub fn scope(namespace: impl ToString, database: impl ToString, scope: impl ToString) -> Self {
+        Self::Scope {
+            namespace: namespace.to_string(),
+            database: database.to_string(),
+            scope: scope.to_string(),
+            params: Map::new(),
+        }
+    }
+
+    /// Add a value for SCOPE credentials to sign in with. Does nothing for
+    /// other credentials
+    pub fn param(mut self, name: impl ToString, value: impl Into<Value>) -> Self {
+        if let Self::Scope { params, .. } = &mut self {
+            params.insert(name.to_string(), value.into());
+        }
+        self
+    }
+
+    /// Change root credentials into namespace or database credentials, for the
+    /// `auth` option of a connection string: `root`, `ns` or `db`. Returns
+    /// `None` for any other level
+    pub(crate) fn at_level(self, level: &str, ns: &str, db: &str) -> Option<Self> {
+        let (user, pass) = match self {
+            Credentials::Basic { user, pass }
+            | Credentials::Namespace { user, pass, .. }
+            | Credentials::Database { user, pass, .. } => (user, pass),
+            other => return Some(other),
+        };
+
+        match level {
+            "root" => Some(Credentials::Basic { user, pass }),
+            "ns" | "namespace" => Some(Credentials::Namespace { ns: ns.to_string(), user, pass }),
+            "db" | "database" => Some(Credentials::Database { ns: ns.to_string(), db: db.to_string(), user, pass }),
+            _ => None,
+        }
+    }
+
+    /// What to send with a `signin` or `signup` request
+    pub(crate) fn signin_params(&self) -> Value {
+        let mut vals = Map::new();
+
+        match self {
+            Credentials::User { user } => {
+                vals.insert("user".to_string(), Value::String(user.clone()));
+            }
+            Credentials::Basic { user, pass } => {
+                vals.insert("user".to_string(), Value::String(user.clone()));
+                vals.insert("pass".to_string(), Value::String(pass.clone()));
+            }
+            Credentials::Namespace { ns, user, pass } => {
+                vals.insert("NS".to_string(), Value::String(ns.clone()));
+                vals.insert("user".to_string(), Value::String(user.clone()));
+                vals.insert("pass".to_string(), Value::String(pass.clone()));
+            }
+            Credentials::Database { ns, db, user, pass } => {
+                vals.insert("NS".to_string(), Value::String(ns.clone()));
+                vals.insert("DB".to_string(), Value::String(db.clone()));
+                vals.insert("user".to_string(), Value::String(user.clone()));
+                vals.insert("pass".to_string(), Value::String(pass.clone()));
+            }
+            Credentials::Scope { namespace, database, scope, params } => {
+                vals.insert("NS".to_string(), Value::String(namespace.clone()));
+                vals.insert("DB".to_string(), Value::String(database.clone()));
+                vals.insert("SC".to_string(), Value::String(scope.clone()));
+                vals.extend(params.iter().map(|(k, v)| (k.clone(), v.clone())));
+            }
+            Credentials::Token { .. } => {}
+        }
+
+        Value::Object(vals)
+    }
+
+    /// SCOPE credentials can't be sent as a header, so have to be
+    /// exchanged for a token with [`signin`](crate::prelude::SurrealClient::signin) first
     pub fn auth_headers(&self) -> Vec<(String, String)> {
         let mut headers = Vec::new();
         let auth_str = match self {
             Credentials::User { user } => {
                 let base = encode(format!("{user}:"));
-                format!("Basic {base}")
+                Some(format!("Basic {base}"))
             }
             Credentials::Basic { user, pass } => {
                 let base = encode(format!("{user}:{pass}"));
-                format!("Basic {base}")
+                Some(format!("Basic {base}"))
+            }
+            Credentials::Namespace { ns, user, pass } => {
+                headers.push(("Surreal-Auth-NS".to_string(), ns.clone()));
+                let base = encode(format!("{user}:{pass}"));
+                Some(format!("Basic {base}"))
             }
+            Credentials::Database { ns, db, user, pass } => {
+                headers.push(("Surreal-Auth-NS".to_string(), ns.clone()));
+                headers.push(("Surreal-Auth-DB".to_string(), db.clone()));
+                let base = encode(format!("{user}:{pass}"));
+                Some(format!("Basic {base}"))
+            }
+            Credentials::Token { token } => Some(format!("Bearer {token}")),
+            Credentials::Scope { .. } => None,
         };
 
-        headers.push(("authorization".to_string(), auth_str));
+        if let Some(auth_str) = auth_str {
+            headers.push(("authorization".to_string(), auth_str));
+        }
 
         headers.push((
             "Access-Control-Allow-Credentials".to_string(),
@@ -36,3 +149,63 @@ impl Credentials {
         headers
     }
 }
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn signin_params() {
+        let user = Credentials::User { user: "ann".to_string() };
+        assert_eq!(user.signin_params(), json!({ "user": "ann" }));
+
+        let root = Credentials::Basic { user: "root".to_string(), pass: "pw".to_string() };
+        assert_eq!(root.signin_params(), json!({ "user": "root", "pass": "pw" }));
+
+        let ns = Credentials::Namespace { ns: "test".to_string(), user: "ann".to_string(), pass: "pw".to_string() };
+        assert_eq!(ns.signin_params(), json!({ "NS": "test", "user": "ann", "pass": "pw" }));
+
+        let db = Credentials::Database { ns: "test".to_string(), db: "demo".to_string(), user: "ann".to_string(), pass: "pw".to_string() };
+        assert_eq!(db.signin_params(), json!({ "NS": "test", "DB": "demo", "user": "ann", "pass": "pw" }));
+
+        let scope = Credentials::scope("test", "demo", "reader").param("email", "ann@example.com");
+        assert_eq!(scope.signin_params(), json!({ "NS": "test", "DB": "demo", "SC": "reader", "email": "ann@example.com" }));
+
+        let token = Credentials::Token { token: "a.jwt".to_string() };
+        assert_eq!(token.signin_params(), json!({}));
+    }
+
+    #[test]
+    fn auth_headers() {
+        let user = Credentials::User { user: "ann".to_string() }.auth_headers();
+        assert_eq!(header(&user, "Authorization"), Some(format!("Basic {}", encode("ann:")).as_str()));
+
+        let root = Credentials::Basic { user: "root".to_string(), pass: "pw".to_string() }.auth_headers();
+        assert_eq!(header(&root, "Authorization"), Some(format!("Basic {}", encode("root:pw")).as_str()));
+        assert_eq!(header(&root, "Surreal-Auth-NS"), None);
+
+        let ns = Credentials::Namespace { ns: "test".to_string(), user: "ann".to_string(), pass: "pw".to_string() }.auth_headers();
+        assert_eq!(header(&ns, "Authorization"), Some(format!("Basic {}", encode("ann:pw")).as_str()));
+        assert_eq!(header(&ns, "Surreal-Auth-NS"), Some("test"));
+        assert_eq!(header(&ns, "Surreal-Auth-DB"), None);
+
+        let db = Credentials::Database { ns: "test".to_string(), db: "demo".to_string(), user: "ann".to_string(), pass: "pw".to_string() }.auth_headers();
+        assert_eq!(header(&db, "Surreal-Auth-NS"), Some("test"));
+        assert_eq!(header(&db, "Surreal-Auth-DB"), Some("demo"));
+
+        let token = Credentials::Token { token: "a.jwt".to_string() }.auth_headers();
+        assert_eq!(header(&token, "Authorization"), Some("Bearer a.jwt"));
+
+        let scope = Credentials::scope("test", "demo", "reader").auth_headers();
+        assert_eq!(header(&scope, "Authorization"), None);
+        assert_eq!(header(&scope, "Access-Control-Allow-Credentials"), Some("true"));
+    }
+}
@@ -0,0 +1,47 @@
+use ::std::{future::Future, time::Duration};
+use ::futures::future::{select, Either, FutureExt};
+
+use crate::{error::*, retry::sleep};
+
+/// Native requests can move between threads while they wait, so the timer has to as well
+#[cfg(not(target_family = "wasm"))]
+type Timer = ::futures::future::BoxFuture<'static, ()>;
+#[cfg(target_family = "wasm")]
+type Timer = ::futures::future::LocalBoxFuture<'static, ()>;
+
+/// When a request gives up with [`SdbError::NetworkTimeout`]. Several steps can
+/// be run against one deadline, and together they can't take longer than it.
+///
+/// Only run steps which are safe to abandon halfway, like waiting for a reply.
+/// Abandoning a half-written websocket frame would corrupt the socket
+pub(crate) struct Deadline {
+    timer: Option<Timer>,
+}
+
+impl Deadline {
+    /// A deadline `limit` from now, or never
+    pub fn after(limit: Option<Duration>) -> Self {
+        Self {
+            timer: limit.map(timer),
+        }
+    }
+
+    /// Run `step`, unless the deadline passes first
+    pub async fn run<T>(&mut self, step: impl Future<Output = SdbResult<T>>) -> SdbResult<T> {
+        let Some( timer ) = self.timer.as_mut() else { return step.await };
+        match select(Box::pin(step), timer).await {
+            Either::Left((result, _)) => result,
+            Either::Right(_) => Err(SdbError::NetworkTimeout),
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn timer(limit: Duration) -> Timer {
+    sleep(limit).boxed()
+}
+
+#[cfg(target_family = "wasm")]
+fn timer(limit: Duration) -> Timer {
+    sleep(limit).boxed_local()
+}
@@ -21,18 +21,70 @@ pub enum SdbError {
         message: SurrealResponseError,
     },
 
+    /// The server rejected the request as a whole, so no statements ran
+    QueryFailed {
+        sql: String,
+        code: isize,
+        message: String,
+    },
+
+    /// One statement in a transaction failed. `index` counts from zero, and
+    /// includes statements which aren't parsed, like `LET`
+    StatementFailed {
+        index: usize,
+        sql: String,
+        detail: String,
+    },
+
+    /// An [`atomic`](crate::prelude::TransactionBuilder::atomic) transaction
+    /// was cancelled, and none of its statements were applied. `index` is the
+    /// statement which caused it, counted like [`SdbError::StatementFailed`]
+    TransactionFailed {
+        index: usize,
+        sql: String,
+        detail: String,
+    },
+
+    /// [`QueryReply::parse`](crate::prelude::QueryReply::parse) was asked for a
+    /// different number of results than there are statements left to read
+    StatementCountMismatch {
+        expected: usize,
+        found: usize,
+    },
+
+    /// The server replied without any statement results
+    EmptyResponse {
+        sql: String,
+    },
+
+    /// Tried to read more statement results than the transaction produced
+    StatementOutOfRange {
+        index: usize,
+        count: usize,
+    },
+
+    /// No statement was added to the transaction with this name, by
+    /// [`push_named`](crate::prelude::TransactionBuilder::push_named)
+    UnknownStatement {
+        name: String,
+    },
+
     /// Hoststring match this format (without spaces):
     /// ```html
-    /// [ <protocol>:// ] [ <username> [ : <password> ] @ ] <url_with_port> / <namespace> / <database>
+    /// [ <protocol>:// ] [ <username> [ : <password> ] @ ] <host> [ : <port> ] [ / <namespace> [ / <database> ] ] [ ? <options> ]
     /// ```
     ///
     /// ### Examples
     /// ```ini
     ///   ws://test_user:test_pass@127.0.0.1:8934/test/demo
-    /// http://                    127.0.0.1:8000/example_ns/demo_db
+    /// http://                    127.0.0.1:8000/example_ns/demo_db?timeout=5s
     /// ```
+    ///
+    /// See [`ServerInfo::new`](crate::prelude::ServerInfo::new) for the options.
+    /// `reason` says what's wrong with it
     InvalidHostString {
         found: String,
+        reason: String,
     },
 
     QueryResultParseFailure {
@@ -42,6 +94,20 @@ pub enum SdbError {
         value: Option<Value>,
     },
 
+    /// Data to send with a request, like the changes for a
+    /// [`merge`](crate::prelude::SurrealClient::merge), couldn't be serialized
+    SerializeFailure {
+        what: String,
+        serde_err: serde_json::Error,
+    },
+
+    /// A value passed to [`push_var`](crate::prelude::TransactionBuilder::push_var)
+    /// or [`set_param`](crate::prelude::SurrealClient::set_param) couldn't be serialized
+    InvalidVariable {
+        name: String,
+        serde_err: serde_json::Error,
+    },
+
     ConnectionClosed {
         info: String,
         url: String,
@@ -66,9 +132,67 @@ pub enum SdbError {
         url: String,
     },
 
-    ///
+    /// The request was too large for the websocket to send
     OversizedPayload,
 
+    /// A secure connection couldn't be set up. Either a certificate or
+    /// identity is invalid, or `sdb` was built without the `tls` feature
+    TlsError {
+        reason: String,
+    },
+
+    /// A client couldn't be configured from the environment or a config file.
+    /// `origin` is `environment`, or the file's path
+    InvalidConfig {
+        origin: String,
+        reason: String,
+    },
+
+    /// The interface the client was built with can't do this, like live
+    /// queries over HTTP
+    Unsupported {
+        what: String,
+    },
+
+    /// A request was missing a parameter, or had one of the wrong type. Only
+    /// hand-built [`SurrealRequest`](crate::prelude::SurrealRequest)s can be
+    InvalidRequest {
+        reason: String,
+    },
+
+    /// A [`MockInterface`](crate::prelude::MockInterface) or
+    /// [`ReplayInterface`](crate::prelude::ReplayInterface) got a request it
+    /// has no reply for
+    UnexpectedRequest {
+        sql: String,
+    },
+
+    /// The reply to a request was for a different one. Usually a custom
+    /// [`SurrealInterface`](crate::prelude::SurrealInterface) or
+    /// [`Layer`](crate::prelude::Layer) replied with the wrong id
+    MismatchedReply {
+        expected: u32,
+        found: u32,
+    },
+
+    /// A cassette of recorded requests couldn't be read or written
+    CassetteError {
+        path: String,
+        reason: String,
+    },
+
+    /// A [`blocking`](crate::blocking) client couldn't start the runtime it
+    /// runs requests on
+    RuntimeError {
+        reason: String,
+    },
+
+    /// Every connection in a [`SurrealPool`](crate::prelude::SurrealPool) stayed
+    /// checked out for longer than its `checkout_timeout`
+    PoolTimeout {
+        waited: std::time::Duration,
+    },
+
     // Non-specific. Ideally all errors below will be converted into
     // one of the errors above instead of being passed.
 
@@ -100,30 +224,198 @@ impl Debug for SdbError {
             Self::UnableToParseAsRecordId { input } => f.debug_struct("UnableToParseAsRecordId").field("input", input).finish(),
             Self::ServerNotSurreal { why } => f.debug_struct("ServerNotSurreal").field("why", why).finish(),
             Self::QuerySyntaxError { query, message } => f.debug_struct("QuerySyntaxError").field("query", query).field("message", message).finish(),
-            Self::InvalidHostString { found } => f.debug_struct("InvalidHostString").field("found", found).finish(),
+            Self::QueryFailed { sql, code, message } => f.debug_struct("QueryFailed").field("sql", sql).field("code", code).field("message", message).finish(),
+            Self::StatementFailed { index, sql, detail } => f.debug_struct("StatementFailed").field("index", index).field("sql", sql).field("detail", detail).finish(),
+            Self::TransactionFailed { index, sql, detail } => f.debug_struct("TransactionFailed").field("index", index).field("sql", sql).field("detail", detail).finish(),
+            Self::EmptyResponse { sql } => f.debug_struct("EmptyResponse").field("sql", sql).finish(),
+            Self::StatementCountMismatch { expected, found } => f.debug_struct("StatementCountMismatch").field("expected", expected).field("found", found).finish(),
+            Self::StatementOutOfRange { index, count } => f.debug_struct("StatementOutOfRange").field("index", index).field("count", count).finish(),
+            Self::UnknownStatement { name } => f.debug_struct("UnknownStatement").field("name", name).finish(),
+            Self::MismatchedReply { expected, found } => f.debug_struct("MismatchedReply").field("expected", expected).field("found", found).finish(),
+            Self::InvalidHostString { found, reason } => f.debug_struct("InvalidHostString").field("found", found).field("reason", reason).finish(),
+            Self::InvalidVariable { name, serde_err } => f.debug_struct("InvalidVariable").field("name", name).field("serde_err", serde_err).finish(),
+            Self::SerializeFailure { what, serde_err } => f.debug_struct("SerializeFailure").field("what", what).field("serde_err", serde_err).finish(),
             Self::ConnectionClosed { info, url } => f.debug_struct("ConnectionClosed").field("info", info).field("url", url).finish(),
             Self::ZeroQueryResults { query } => f.debug_struct("ZeroQueryResults").field("query", query).finish(),
             Self::QueryTimeout => write!(f, "QueryTimeout"),
             Self::NetworkTimeout => write!(f, "NetworkTimeout"),
             Self::ConnectionRefused { url } => f.debug_struct("ConnectionRefused").field("url", url).finish(),
             Self::OversizedPayload => write!(f, "OversizedPayload"),
+            Self::TlsError { reason } => f.debug_struct("TlsError").field("reason", reason).finish(),
+            Self::InvalidConfig { origin, reason } => f.debug_struct("InvalidConfig").field("origin", origin).field("reason", reason).finish(),
+            Self::Unsupported { what } => f.debug_struct("Unsupported").field("what", what).finish(),
+            Self::InvalidRequest { reason } => f.debug_struct("InvalidRequest").field("reason", reason).finish(),
+            Self::UnexpectedRequest { sql } => f.debug_struct("UnexpectedRequest").field("sql", sql).finish(),
+            Self::CassetteError { path, reason } => f.debug_struct("CassetteError").field("path", path).field("reason", reason).finish(),
+            Self::RuntimeError { reason } => f.debug_struct("RuntimeError").field("reason", reason).finish(),
+            Self::PoolTimeout { waited } => f.debug_struct("PoolTimeout").field("waited", waited).finish(),
             
             // x86 only
             #[cfg(all(feature = "http", not(target_family = "wasm")))]
             Self::HttpNetowrkError(arg0) => f.debug_tuple("HttpNetowrkError").field(arg0).finish(),
-            #[cfg(all(feature = "http", not(target_family = "wasm")))]
+            #[cfg(all(feature = "ws", not(target_family = "wasm")))]
             Self::WebsocketNetworkError(arg0) => f.debug_tuple("WebsocketNetworkError").field(arg0).finish(),
 
             // wasm only 
             #[cfg(all(feature = "http", target_family = "wasm"))]
             Self::HttpNetowrkError(arg0) => f.debug_tuple("HttpNetowrkError").field(arg0).finish(),
-            #[cfg(all(feature = "http", target_family = "wasm"))]
+            #[cfg(all(feature = "ws", target_family = "wasm"))]
             Self::WebsocketNetworkError(arg0) => f.debug_tuple("WebsocketNetworkError").field(arg0).finish(),
         }
     }
 }
 
+impl Display for SdbError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::UnableToParseAsRecordId { input } => write!(f, "{input:?} is not a valid record id"),
+            Self::ServerNotSurreal { why } => write!(f, "The server is not a SurrealDB instance: {why}"),
+            Self::QuerySyntaxError { query, message } => write!(f, "Syntax error in query ({}): {}\n{query}", message.code, message.message),
+            Self::QueryFailed { sql, code, message } => write!(f, "Query failed ({code}): {message}\n{sql}"),
+            Self::StatementFailed { index, sql, detail } => write!(f, "Statement {index} failed: {detail}\n{sql}"),
+            Self::TransactionFailed { index, sql, detail } => write!(f, "Transaction cancelled by statement {index}, nothing was applied: {detail}\n{sql}"),
+            Self::EmptyResponse { sql } => write!(f, "The server replied without any results\n{sql}"),
+            Self::StatementCountMismatch { expected, found } => write!(f, "Tried to parse {expected} statement results, but there are {found} left"),
+            Self::StatementOutOfRange { index, count } => write!(f, "Tried to read statement {index}, but the transaction only has {count}"),
+            Self::UnknownStatement { name } => write!(f, "No statement in the transaction is named {name:?}"),
+            Self::MismatchedReply { expected, found } => write!(f, "Expected the reply to request {expected}, but got the reply to {found}"),
+            Self::InvalidHostString { found, reason } => write!(f, "Invalid connection string {found:?}: {reason}"),
+            Self::QueryResultParseFailure { target_type, serde_err, query, .. } => write!(f, "Failed to parse result as {target_type}: {serde_err}\n{query}"),
+            Self::InvalidVariable { name, serde_err } => write!(f, "Cannot serialize value into variable `${name}`: {serde_err}"),
+            Self::SerializeFailure { what, serde_err } => write!(f, "Cannot serialize the data for {what}: {serde_err}"),
+            Self::ConnectionClosed { info, url } => write!(f, "Connection to {url} closed: {info}"),
+            Self::ZeroQueryResults { query } => write!(f, "Expected a result, but found none\n{query}"),
+            Self::QueryTimeout => write!(f, "The query took longer than its TIMEOUT clause allowed"),
+            Self::NetworkTimeout => write!(f, "The server took too long to respond"),
+            Self::ConnectionRefused { url } => write!(f, "Connection to {url} refused"),
+            Self::OversizedPayload => write!(f, "The payload was too large to send"),
+            Self::TlsError { reason } => write!(f, "Unable to set up a secure connection: {reason}"),
+            Self::InvalidConfig { origin, reason } => write!(f, "Invalid configuration in {origin}: {reason}"),
+            Self::Unsupported { what } => write!(f, "{what} are not supported by this interface"),
+            Self::InvalidRequest { reason } => write!(f, "Invalid request: {reason}"),
+            Self::UnexpectedRequest { sql } => write!(f, "No reply was scripted or recorded for:\n{sql}"),
+            Self::CassetteError { path, reason } => write!(f, "Failed to use cassette {path}: {reason}"),
+            Self::RuntimeError { reason } => write!(f, "Failed to start the blocking client's runtime: {reason}"),
+            Self::PoolTimeout { waited } => write!(f, "No pooled connection was returned within {waited:?}"),
+
+            #[cfg(feature = "http")]
+            Self::HttpNetowrkError(err) => write!(f, "HTTP network error: {err}"),
+            #[cfg(feature = "ws")]
+            Self::WebsocketNetworkError(err) => write!(f, "Websocket network error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SdbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::QueryResultParseFailure { serde_err, .. } => Some(serde_err),
+            Self::InvalidVariable { serde_err, .. } => Some(serde_err),
+            Self::SerializeFailure { serde_err, .. } => Some(serde_err),
+
+            #[cfg(feature = "http")]
+            Self::HttpNetowrkError(err) => Some(err),
+            #[cfg(feature = "ws")]
+            Self::WebsocketNetworkError(err) => Some(err),
+
+            _ => None,
+        }
+    }
+}
+
 impl SdbError {
+    /// Lost, or couldn't reach, the server. Nothing is known about whether the
+    /// request ran
+    pub fn is_connection(&self) -> bool {
+        match self {
+            Self::ConnectionClosed { .. }
+            | Self::ConnectionRefused { .. }
+            | Self::NetworkTimeout => true,
+
+            #[cfg(feature = "http")]
+            Self::HttpNetowrkError(_) => true,
+            #[cfg(feature = "ws")]
+            Self::WebsocketNetworkError(_) => true,
+
+            _ => false,
+        }
+    }
+
+    /// Took too long, either on the network or because of a **TIMEOUT** clause
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::QueryTimeout | Self::NetworkTimeout)
+    }
+
+    /// The SQL couldn't be parsed by the server
+    pub fn is_syntax(&self) -> bool {
+        match self {
+            Self::QuerySyntaxError { .. } => true,
+            _ => self.server_message().is_some_and(|msg| msg.contains("Parse error")),
+        }
+    }
+
+    /// The credentials were rejected, or don't allow what was attempted
+    pub fn is_auth(&self) -> bool {
+        let Some( msg ) = self.server_message() else { return false };
+        let msg = msg.to_lowercase();
+        msg.contains("authentication") || msg.contains("permission") || msg.contains("not allowed")
+    }
+
+    /// Trying the same thing again might work. True for connection problems,
+    /// timeouts, conflicts with other transactions, and transactions the server
+    /// says can be retried.
+    ///
+    /// A request which timed out or lost its connection might still have been
+    /// applied, so this is only safe to act on when running it twice does no
+    /// harm. See [`was_not_applied`](SdbError::was_not_applied) otherwise
+    pub fn is_retryable(&self) -> bool {
+        self.is_connection() || self.is_timeout() || self.is_conflict()
+    }
+
+    /// The server certainly didn't apply the request, so it can be sent again:
+    /// the server cancelled it over a conflict, or it never reached the server
+    pub fn was_not_applied(&self) -> bool {
+        matches!(self, Self::ConnectionRefused { .. }) || self.is_conflict()
+    }
+
+    /// The server cancelled the transaction because of another, or said it can
+    /// be retried
+    fn is_conflict(&self) -> bool {
+        self.server_message().is_some_and(|msg| {
+            let msg = msg.to_lowercase();
+            msg.contains("can be retried") || msg.contains("conflict") || msg.contains("resource busy")
+        })
+    }
+
+    /// What the server said went wrong, if this error came from the server
+    fn server_message(&self) -> Option<&str> {
+        match self {
+            Self::QuerySyntaxError { message, .. } => Some(&message.message),
+            Self::QueryFailed { message, .. } => Some(message),
+            Self::StatementFailed { detail, .. } => Some(detail),
+            Self::TransactionFailed { detail, .. } => Some(detail),
+            _ => None,
+        }
+    }
+
+    /// Turn an error reply from the server into the matching variant
+    pub(crate) fn from_response(sql: impl ToString, error: SurrealResponseError) -> Self {
+        if error.message.contains("Parse error") {
+            SdbError::QuerySyntaxError {
+                query: sql.to_string(),
+                message: error,
+            }
+        } else if error.message.contains("exceeded the timeout") {
+            SdbError::QueryTimeout
+        } else {
+            SdbError::QueryFailed {
+                sql: sql.to_string(),
+                code: error.code,
+                message: error.message,
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn parse_failure<T>(reply: &crate::reply::StatementResult, err: serde_json::Error) -> Self {
         SdbError::QueryResultParseFailure {
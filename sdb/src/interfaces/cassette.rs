@@ -0,0 +1,285 @@
+use ::std::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use ::serde::{Deserialize, Serialize};
+use ::serde_json::Value;
+
+#[cfg(feature = "ws")]
+use crate::client::LiveReceiver;
+use crate::{
+    client::interface::*,
+    error::{SdbError, SdbResult},
+    server_info::ServerInfo,
+};
+
+/// One request, and the reply it got
+#[derive(Serialize, Deserialize)]
+struct Interaction {
+    request: SurrealRequest,
+    response: SurrealResponse,
+}
+
+/// Sends requests through another interface, and writes each of them and its
+/// reply to a JSON cassette, for a [`ReplayInterface`] to serve later.
+///
+/// ```rust
+/// # use sdb::prelude::*;
+/// let builder = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo");
+/// let recorder = RecordingInterface::new(
+///     builder.clone().build_interface().unwrap(),
+///     "target/books.cassette.json",
+/// );
+/// let client = builder.build_with(recorder).unwrap();
+/// ```
+///
+/// The cassette is rewritten after every reply, so it's complete even if the
+/// client is never dropped. Requests which fail before the server replies
+/// aren't recorded, and neither are those which only change the session, like
+/// `signin` or `authenticate`. A replay doesn't need them, and they carry
+/// credentials and tokens
+pub struct RecordingInterface {
+    inner: Box<dyn SurrealInterface>,
+    path: PathBuf,
+    cassette: Mutex<Vec<Interaction>>,
+}
+
+impl RecordingInterface {
+    /// Record the requests sent through `inner` to the cassette at `path`,
+    /// replacing any which is already there
+    pub fn new(inner: Box<dyn SurrealInterface>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn record(&self, request: SurrealRequest, response: &SurrealResponse) -> SdbResult<()> {
+        if request.sql().is_none() {
+            return Ok(())
+        }
+
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette.push(Interaction {
+            request,
+            response: response.clone(),
+        });
+
+        let json = serde_json::to_string_pretty(&*cassette)
+            .map_err(|err| cassette_err(&self.path, err))?;
+        fs::write(&self.path, json).map_err(|err| cassette_err(&self.path, err))
+    }
+}
+
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+impl SurrealInterface for RecordingInterface {
+    async fn execute(
+        &self,
+        server: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        let response = self.inner.execute(server, request.clone()).await?;
+        self.record(request, &response)?;
+        Ok(response)
+    }
+
+    #[cfg(feature = "ws")]
+    fn subscribe(&self, live_id: &str) -> SdbResult<LiveReceiver> {
+        self.inner.subscribe(live_id)
+    }
+
+    #[cfg(feature = "ws")]
+    fn unsubscribe(&self, live_id: &str) {
+        self.inner.unsubscribe(live_id)
+    }
+
+    #[cfg(feature = "ws")]
+    fn kill_live(&self, live_id: &str) {
+        self.inner.kill_live(live_id)
+    }
+}
+
+impl Debug for RecordingInterface {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RecordingInterface")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Replies to requests with those recorded by a [`RecordingInterface`], without
+/// a server. Requests are matched by their SQL, ignoring differences in
+/// whitespace, and by the vars or data sent with it. The random ids they're
+/// sent with don't matter.
+///
+/// Matching requests get the replies recorded for them in order, and
+/// the last one after those run out. Requests which only change the session,
+/// like `use` or `let`, always succeed. Any other request fails with
+/// [`SdbError::UnexpectedRequest`]
+///
+/// ```rust,no_run
+/// # use sdb::prelude::*;
+/// let replay = ReplayInterface::open("tests/books.cassette.json").unwrap();
+/// let client = SurrealClient::open("127.0.0.1:8000/example/demo")
+///     .build_with(replay)
+///     .unwrap();
+/// ```
+pub struct ReplayInterface {
+    path: PathBuf,
+    replies: Mutex<Vec<Replay>>,
+}
+
+struct Replay {
+    key: ReplayKey,
+    response: SurrealResponse,
+    used: bool,
+}
+
+/// What a request is matched by: its SQL, then its vars or data
+#[derive(PartialEq)]
+struct ReplayKey {
+    sql: String,
+    params: Vec<Value>,
+}
+
+impl ReplayKey {
+    /// `None` for requests which only change the session
+    fn of(request: &SurrealRequest) -> Option<Self> {
+        Some(Self {
+            sql: normalize(&request.sql()?),
+            params: request.params.iter().skip(1).cloned().collect(),
+        })
+    }
+}
+
+impl ReplayInterface {
+    /// Read the cassette at `path`
+    pub fn open(path: impl AsRef<Path>) -> SdbResult<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|err| cassette_err(path, err))?;
+        let cassette = serde_json::from_str::<Vec<Interaction>>(&json)
+            .map_err(|err| cassette_err(path, err))?;
+
+        let replies = cassette
+            .into_iter()
+            .filter_map(|Interaction { request, response }| {
+                let key = ReplayKey::of(&request)?;
+                Some(Replay { key, response, used: false })
+            })
+            .collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            replies: Mutex::new(replies),
+        })
+    }
+
+    /// The first unused reply to `key`, or the last one if they've all been used
+    fn reply_to(&self, key: &ReplayKey) -> Option<SurrealResponse> {
+        let mut replies = self.replies.lock().unwrap();
+        let mut matching = replies.iter_mut().filter(|r| r.key == *key).peekable();
+
+        let mut last = None;
+        while let Some( replay ) = matching.next() {
+            if !replay.used || matching.peek().is_none() {
+                replay.used = true;
+                last = Some(replay.response.clone());
+                break;
+            }
+        }
+        last
+    }
+}
+
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+impl SurrealInterface for ReplayInterface {
+    async fn execute(
+        &self,
+        _server: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        let id = request.id;
+        let Some( key ) = ReplayKey::of(&request) else {
+            return Ok(SurrealResponse::Result { id, result: None })
+        };
+
+        match self.reply_to(&key) {
+            Some( response ) => Ok(response.with_id(id)),
+            None => Err(SdbError::UnexpectedRequest { sql: request.sql().unwrap_or_default() }),
+        }
+    }
+}
+
+impl Debug for ReplayInterface {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ReplayInterface")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// `sql` with its whitespace collapsed, and without a trailing `;`
+fn normalize(sql: &str) -> String {
+    let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    sql.trim_end_matches(';').trim_end().to_string()
+}
+
+fn cassette_err(path: &Path, err: impl ToString) -> SdbError {
+    SdbError::CassetteError {
+        path: path.display().to_string(),
+        reason: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::serde_json::json;
+
+    use super::*;
+    use crate::prelude::*;
+
+    const SQL: &str = "SELECT * FROM books WHERE title = $title";
+
+    fn find(client: &SurrealClient, title: &str) -> SdbResult<Value> {
+        tokio_test::block_on(async {
+            client.transaction()
+                .push_var("title", title)
+                .push(SQL)
+                .run()
+                .await?
+                .next_one::<Value>()
+        })
+    }
+
+    #[test]
+    fn replays_what_was_recorded() {
+        let path = std::env::temp_dir().join(format!("sdb-cassette-{}.json", std::process::id()));
+        let mock = MockInterface::new()
+            .expect(SQL, vec![StatementResult::ok(json!([{ "title": "Dune" }]))])
+            .expect(SQL, vec![StatementResult::ok(json!([{ "title": "Emma" }]))]);
+
+        let recorder = RecordingInterface::new(Box::new(mock.clone()), &path);
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(recorder).unwrap();
+        let creds = Credentials::Basic { user: "root".to_string(), pass: "hunter2".to_string() };
+        tokio_test::block_on(client.signin(creds)).unwrap();
+        find(&client, "Dune").unwrap();
+        find(&client, "Emma").unwrap();
+        mock.assert_met();
+
+        let cassette = fs::read_to_string(&path).unwrap();
+        assert!(!cassette.contains("hunter2"), "The cassette has the password in it");
+
+        let replay = ReplayInterface::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(replay).unwrap();
+
+        assert_eq!(find(&client, "Emma").unwrap()["title"], "Emma");
+        assert_eq!(find(&client, "Dune").unwrap()["title"], "Dune");
+        assert!(matches!(find(&client, "Persuasion"), Err(SdbError::UnexpectedRequest { .. })));
+    }
+}
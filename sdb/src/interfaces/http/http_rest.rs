@@ -1,9 +1,11 @@
-use ::reqwest::{Client, ClientBuilder, Error as ReqError, RequestBuilder, Url};
-use ::serde_json::Value;
+use ::reqwest::{Client, ClientBuilder, Error as ReqError, Method, RequestBuilder, Url};
+use ::serde_json::{Map, Value};
 
 use crate::{
     client::interface::*,
+    deadline::Deadline,
     error::{SdbError, SdbResult},
+    record::Resource,
     reply::StatementResult,
     server_info::ServerInfo,
 };
@@ -14,18 +16,31 @@ pub struct HttpSurrealInterface {
 }
 
 impl SurrealInterfaceBuilder for HttpSurrealInterface {
-    fn new(_info: &ServerInfo) -> SdbResult<Self> {
-        let client = ClientBuilder::new().build().unwrap();
+    fn new(info: &ServerInfo) -> SdbResult<Self> {
+        let builder = ClientBuilder::new();
+
+        #[cfg(feature = "tls")]
+        let builder = match info.protocol.is_secure() {
+            true => info.tls.apply_reqwest(builder)?,
+            false => builder,
+        };
+
+        let client = builder.build().map_err(|e| convert_err(e, info))?;
 
         Ok(Self { client })
     }
 }
 
 impl HttpSurrealInterface {
-    fn request(&self, info: &ServerInfo) -> Result<RequestBuilder, SdbError> {
-        let url = Url::parse(&info.full_url()).expect("A valid hostname for the database");
+    fn request_to(&self, info: &ServerInfo, method: Method, url: &str) -> Result<RequestBuilder, SdbError> {
+        let Ok( url ) = Url::parse(url) else {
+            return Err(SdbError::InvalidHostString {
+                found: url.to_string(),
+                reason: "Not a valid URL".to_string(),
+            })
+        };
 
-        let mut req = self.client.post(url);
+        let mut req = self.client.request(method, url);
 
         for (k, v) in info.headers() {
             req = req.header(k, v);
@@ -34,60 +49,140 @@ impl HttpSurrealInterface {
         Ok(req)
     }
 
-    async fn execute_query( &self, info: &ServerInfo, sql: impl ToString ) -> SdbResult<Vec<StatementResult>> {
-        let req = self.request(info)?.body( sql.to_string() );
+    async fn execute_query( &self, info: &ServerInfo, sql: &str, vars: Option<&Map<String, Value>> ) -> SdbResult<Vec<StatementResult>> {
+        let url = super::sql_url(info, vars)?;
+        let req = self.request_to(info, Method::POST, &url)?.body( sql.to_string() );
         let res = req.send().await.map_err(|e| convert_err(e, info))?;
         let txt = res.text().await.map_err(|e| convert_err(e, info))?;
         match serde_json::from_str::<Vec<StatementResult>>(&txt) {
-            Err(_err) => unreachable!("Response Parse Failure"),
+            Err(serde_err) => Err(SdbError::QueryResultParseFailure {
+                query: sql.to_string(),
+                target_type: "Vec<StatementResult>".to_string(),
+                serde_err,
+                value: serde_json::from_str(&txt).ok(),
+            }),
             Ok(replies) => Ok(replies),
         }
     }
-}
 
-#[async_trait::async_trait(?Send)]
-impl SurrealInterface for HttpSurrealInterface {
-    async fn execute(
-        &mut self,
+    /// Run a `select`, `create`, etc. against the `/key` endpoint
+    async fn execute_key( &self, info: &ServerInfo, verb: &str, what: &Resource, body: Option<&Value> ) -> SdbResult<Value> {
+        let method = Method::from_bytes(verb.as_bytes()).unwrap_or(Method::GET);
+        let mut req = self.request_to(info, method, &info.key_url(what))?;
+        if let Some( body ) = body {
+            req = req.body( body.to_string() );
+        }
+
+        let res = req.send().await.map_err(|e| convert_err(e, info))?;
+        let txt = res.text().await.map_err(|e| convert_err(e, info))?;
+        match serde_json::from_str::<Vec<StatementResult>>(&txt) {
+            Err(serde_err) => Err(SdbError::QueryResultParseFailure {
+                query: format!("{verb} {}", info.key_url(what)),
+                target_type: "Vec<StatementResult>".to_string(),
+                serde_err,
+                value: serde_json::from_str(&txt).ok(),
+            }),
+            Ok(replies) => super::key_result(replies, format!("{verb} {what}")),
+        }
+    }
+
+    /// Sign in or up through the `/signin` or `/signup` endpoint
+    async fn execute_auth( &self, info: &ServerInfo, request: &SurrealRequest, endpoint: &str ) -> SdbResult<SurrealResponse> {
+        let body = request.params.first().cloned().unwrap_or_default();
+        let req = self.request_to(info, Method::POST, &info.endpoint_url(endpoint))?
+            .body( body.to_string() );
+
+        let res = req.send().await.map_err(|e| convert_err(e, info))?;
+        let txt = res.text().await.map_err(|e| convert_err(e, info))?;
+        super::auth_result(request.id, &txt, &endpoint.to_uppercase())
+    }
+
+    /// Send a request to whichever endpoint handles it
+    async fn dispatch(
+        &self,
         info: &ServerInfo,
         request: SurrealRequest,
     ) -> SdbResult<SurrealResponse> {
 
         match request.method {
-            RequestMethod::Ping => unreachable!(),
-            RequestMethod::Info => todo!(),
-            RequestMethod::Select => todo!(),
-            RequestMethod::Create => todo!(),
-            RequestMethod::Update => todo!(),
-            RequestMethod::Merge => todo!(),
-            RequestMethod::Patch => todo!(),
-            RequestMethod::Delete => todo!(),
-            RequestMethod::Format => todo!(),
-            RequestMethod::Version => todo!(),
+            RequestMethod::Select |
+            RequestMethod::Create |
+            RequestMethod::Update |
+            RequestMethod::Merge |
+            RequestMethod::Delete => {
+                let what = Resource::from(super::str_param(&request, 0)?);
+                let verb = super::key_verb(&request.method).ok_or_else(|| super::unsupported(&request.method))?;
+                let result = self.execute_key(info, verb, &what, request.params.get(1)).await?;
+                Ok( SurrealResponse::Data { id: request.id, result } )
+            },
+            RequestMethod::Patch => {
+                let what = super::str_param(&request, 0)?;
+                let mut vars = Map::new();
+                vars.insert("patch".to_string(), request.params.get(1).cloned().unwrap_or_default());
+                let sql = format!("UPDATE {what} PATCH $patch");
+                let replies = self.execute_query(info, &sql, Some(&vars)).await?;
+                let result = super::key_result(replies, sql)?;
+                Ok( SurrealResponse::Data { id: request.id, result } )
+            },
             RequestMethod::Query => {
-                let Some( Value::String( sql ) ) = request.params.get(0) else { unreachable!() };
-                let replies = self.execute_query(info, sql).await?;
+                let sql = super::str_param(&request, 0)?;
+                let vars = request.params.get(1).and_then(Value::as_object);
+                let replies = self.execute_query(info, sql, vars).await?;
                 Ok( SurrealResponse::Result {
                     id: request.id,
                     result: Some(replies),
                 })
             },
 
+            // Every request carries the namespace, database and params in the
+            // ServerInfo, so there is no session to change
             RequestMethod::Use |
-            RequestMethod::Let | 
-            RequestMethod::Unset => todo!("Don't really make sense to use here"),
-
-            RequestMethod::Kill |
-            RequestMethod::Live => todo!("Unimplementable for this interface"),
+            RequestMethod::Let |
+            RequestMethod::Unset => Ok( SurrealResponse::Result {
+                id: request.id,
+                result: None,
+            }),
 
             RequestMethod::Signup |
-            RequestMethod::Signin |
+            RequestMethod::Signin => {
+                let endpoint = super::auth_endpoint(&request.method).ok_or_else(|| super::unsupported(&request.method))?;
+                self.execute_auth(info, &request, endpoint).await
+            },
+
+            // The token is sent with every request, and the client keeps
+            // track of it, so there's nothing to tell the server
             RequestMethod::Invalidate |
-            RequestMethod::Authenticate => todo!("Alter server-info or do nothing"),
+            RequestMethod::Authenticate => Ok( SurrealResponse::Result {
+                id: request.id,
+                result: None,
+            }),
+
+            // There's no connection to keep alive or hold live queries, and
+            // the rest have no endpoint
+            RequestMethod::Ping |
+            RequestMethod::Info |
+            RequestMethod::Format |
+            RequestMethod::Version |
+            RequestMethod::Kill |
+            RequestMethod::Live => Err(super::unsupported(&request.method)),
         }
     }
 }
 
+#[async_trait::async_trait]
+impl SurrealInterface for HttpSurrealInterface {
+    async fn execute(
+        &self,
+        info: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        // an abandoned HTTP request doesn't affect any other
+        Deadline::after(request.timeout)
+            .run(self.dispatch(info, request))
+            .await
+    }
+}
+
 fn convert_err(base: ReqError, info: &ServerInfo) -> SdbError {
     let url = info.full_url();
 
@@ -99,3 +194,38 @@ fn convert_err(base: ReqError, info: &ServerInfo) -> SdbError {
         SdbError::HttpNetowrkError(base)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(method: RequestMethod, params: Vec<Value>) -> SdbResult<SurrealResponse> {
+        let info = ServerInfo::new("http://127.0.0.1:8000/test/demo", None, None).unwrap();
+        let interface = HttpSurrealInterface::new(&info).unwrap();
+        let request = SurrealRequest { id: 1, method, params, timeout: None };
+        tokio_test::block_on(interface.execute(&info, request))
+    }
+
+    #[test]
+    fn unsupported_methods_fail() {
+        for method in [
+            RequestMethod::Ping,
+            RequestMethod::Info,
+            RequestMethod::Format,
+            RequestMethod::Version,
+            RequestMethod::Kill,
+            RequestMethod::Live,
+        ] {
+            assert!(matches!(run(method, vec![]), Err(SdbError::Unsupported { .. })));
+        }
+    }
+
+    #[test]
+    fn missing_params_fail() {
+        for method in [RequestMethod::Query, RequestMethod::Select, RequestMethod::Patch] {
+            assert!(matches!(run(method, vec![]), Err(SdbError::InvalidRequest { .. })));
+        }
+        let result = run(RequestMethod::Query, vec![Value::from(7)]);
+        assert!(matches!(result, Err(SdbError::InvalidRequest { .. })));
+    }
+}
@@ -1,10 +1,12 @@
 use ::gloo_net::http::{Headers, Method, Request, RequestCredentials};
-use ::serde_json::{from_str,Value};
+use ::serde_json::{from_str, Map, Value};
 
 use crate::{
     client::interface::*,
+    deadline::Deadline,
     error::{SdbError, SdbResult},
-    reply::QueryReply,
+    record::Resource,
+    reply::StatementResult,
     server_info::ServerInfo,
 };
 
@@ -18,38 +20,42 @@ impl SurrealInterfaceBuilder for HttpSurrealInterface {
 }
 
 impl HttpSurrealInterface {
-    fn request(&self, info: &ServerInfo, sql: impl ToString) -> Result<Request, SdbError> {
+    fn request(&self, info: &ServerInfo, method: Method, url: &str, body: Option<String>) -> Result<Request, SdbError> {
         let head = Headers::new();
         for (k, v) in info.headers() {
             head.append(&k, &v)
         }
 
-        let req = Request::new(&info.full_url())
+        let req = Request::new(url)
             .headers(head)
-            .method(Method::POST)
-            .credentials(RequestCredentials::Include)
-            .body(sql.to_string());
+            .method(method)
+            .credentials(RequestCredentials::Include);
 
-        Ok(req)
+        Ok(match body {
+            Some( body ) => req.body(body),
+            None => req,
+        })
     }
-}
-
-unsafe impl Send for HttpSurrealInterface {}
-unsafe impl Sync for HttpSurrealInterface {}
 
-#[async_trait::async_trait(?Send)]
-impl SurrealInterface for HttpSurrealInterface {
-    async fn execute(
-        &mut self,
-        info: &ServerInfo,
-        request: SurrealRequest,
-    ) -> SdbResult<SurrealResponse> {
-        let Some( Value::String( sql ) ) = request.params.get(0) else { panic!() };
-        let req = self.request(info, &sql)?;
+    /// Send a request, and check the reply came from SurrealDB
+    async fn send(&self, req: Request) -> SdbResult<Vec<StatementResult>> {
+        let text = self.send_raw(req).await?;
+        match from_str::<Vec<StatementResult>>(&text) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(SdbError::QueryResultParseFailure {
+                query: String::new(),
+                target_type: "Vec<StatementResult>".to_string(),
+                serde_err: e,
+                value: from_str(&text).ok(),
+            }),
+        }
+    }
 
+    /// Send a request, check the reply came from SurrealDB, and return its body
+    async fn send_raw(&self, req: Request) -> SdbResult<String> {
         let res = match req.send().await {
             Ok(res) => res,
-            Err(err) => panic!("Netork Error: {err:?}"),
+            Err(err) => return Err(SdbError::HttpNetowrkError(err)),
         };
 
         let headers = res.headers();
@@ -61,26 +67,119 @@ impl SurrealInterface for HttpSurrealInterface {
         )?;
         header_check("server", "SurrealDB", headers.get("server"))?;
 
-        let res_text = res.text();
-        let res_text = res_text.await;
-        match &res_text {
-            Ok(text) => match from_str::<Vec<QueryReply>>(&text) {
-                Ok(r) => Ok(SurrealResponse::Result {
+        match res.text().await {
+            Ok(text) => Ok(text),
+            Err(err) => Err(SdbError::ServerNotSurreal {
+                why: format!("Unreadable response: {err:?}"),
+            }),
+        }
+    }
+
+    async fn execute_query(&self, info: &ServerInfo, sql: &str, vars: Option<&Map<String, Value>>) -> SdbResult<Vec<StatementResult>> {
+        let url = super::sql_url(info, vars)?;
+        let req = self.request(info, Method::POST, &url, Some(sql.to_string()))?;
+        self.send(req).await
+    }
+
+    /// Send a request to whichever endpoint handles it
+    async fn dispatch(
+        &self,
+        info: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        match request.method {
+            RequestMethod::Select |
+            RequestMethod::Create |
+            RequestMethod::Update |
+            RequestMethod::Merge |
+            RequestMethod::Delete => {
+                let what = Resource::from(super::str_param(&request, 0)?);
+                let verb = super::key_verb(&request.method).ok_or_else(|| super::unsupported(&request.method))?;
+                let method = match verb {
+                    "POST" => Method::POST,
+                    "PUT" => Method::PUT,
+                    "PATCH" => Method::PATCH,
+                    "DELETE" => Method::DELETE,
+                    _ => Method::GET,
+                };
+                let body = request.params.get(1).map(Value::to_string);
+                let req = self.request(info, method, &info.key_url(&what), body)?;
+                let result = super::key_result(self.send(req).await?, format!("{verb} {what}"))?;
+                Ok(SurrealResponse::Data { id: request.id, result })
+            }
+
+            RequestMethod::Patch => {
+                let what = super::str_param(&request, 0)?;
+                let mut vars = Map::new();
+                vars.insert("patch".to_string(), request.params.get(1).cloned().unwrap_or_default());
+                let sql = format!("UPDATE {what} PATCH $patch");
+                let replies = self.execute_query(info, &sql, Some(&vars)).await?;
+                let result = super::key_result(replies, sql)?;
+                Ok(SurrealResponse::Data { id: request.id, result })
+            }
+
+            RequestMethod::Query => {
+                let sql = super::str_param(&request, 0)?;
+                let vars = request.params.get(1).and_then(Value::as_object);
+                let replies = self.execute_query(info, sql, vars).await?;
+                Ok(SurrealResponse::Result {
                     id: request.id,
-                    result: Some(r),
-                }),
-                Err(e) => Err(SdbError::QueryResultParseFailure {
-                    query: String::new(),
-                    target_type: "Vec<QueryReply>".to_string(),
-                    serde_err: e,
-                    value: None,
-                }),
-            },
-            _ => panic!("Invalid response"),
+                    result: Some(replies),
+                })
+            }
+
+            // Every request carries the namespace, database and params in the
+            // ServerInfo, so there is no session to change
+            RequestMethod::Use |
+            RequestMethod::Let |
+            RequestMethod::Unset => Ok(SurrealResponse::Result {
+                id: request.id,
+                result: None,
+            }),
+
+            RequestMethod::Signup |
+            RequestMethod::Signin => {
+                let endpoint = super::auth_endpoint(&request.method).ok_or_else(|| super::unsupported(&request.method))?;
+                let body = request.params.first().map(Value::to_string);
+                let req = self.request(info, Method::POST, &info.endpoint_url(endpoint), body)?;
+                let text = self.send_raw(req).await?;
+                super::auth_result(request.id, &text, &endpoint.to_uppercase())
+            }
+
+            // The token is sent with every request, and the client keeps
+            // track of it, so there's nothing to tell the server
+            RequestMethod::Invalidate |
+            RequestMethod::Authenticate => Ok(SurrealResponse::Result {
+                id: request.id,
+                result: None,
+            }),
+
+            // There's no connection to keep alive or hold live queries, and
+            // the rest have no endpoint
+            RequestMethod::Ping |
+            RequestMethod::Info |
+            RequestMethod::Format |
+            RequestMethod::Version |
+            RequestMethod::Kill |
+            RequestMethod::Live => Err(super::unsupported(&request.method)),
         }
     }
 }
 
+#[async_trait::async_trait(?Send)]
+impl SurrealInterface for HttpSurrealInterface {
+    async fn execute(
+        &self,
+        info: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        // an abandoned HTTP request doesn't affect any other
+        Deadline::after(request.timeout)
+            .run(self.dispatch(info, request))
+            .await
+    }
+}
+
 fn header_check(header: &str, expect: &str, found: Option<String>) -> SdbResult<()> {
     match found {
         Some(ct) if ct.starts_with(expect) => Ok(()),
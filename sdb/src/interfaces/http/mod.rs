@@ -1,3 +1,12 @@
+use ::serde_json::{Map, Value};
+
+use crate::{
+    client::interface::{RequestMethod, SurrealRequest, SurrealResponse, SurrealResponseError},
+    error::{SdbError, SdbResult},
+    reply::StatementResult,
+    server_info::{url_encode, ServerInfo},
+};
+
 #[cfg(all(feature = "http", target_family = "wasm"))]
 mod http_wasm;
 #[cfg(all(feature = "http", target_family = "wasm"))]
@@ -7,3 +16,144 @@ pub use http_wasm::*;
 mod http_rest;
 #[cfg(all(feature = "http", not(target_family = "wasm")))]
 pub use http_rest::*;
+
+
+/// The `/sql` URL, with the connection-wide params and the request's vars as
+/// query parameters. The server sets each as a variable for the query, like the
+/// websocket `query` method's vars. Strings are sent as they are, and anything
+/// else as JSON
+fn sql_url(info: &ServerInfo, vars: Option<&Map<String, Value>>) -> SdbResult<String> {
+    let mut all = info.params.clone();
+    if let Some( vars ) = vars {
+        all.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    let mut url = info.full_url();
+    for (index, (name, value)) in all.iter().enumerate() {
+        if !is_identifier(name) {
+            return Err(SdbError::InvalidRequest {
+                reason: format!("`${name}` isn't a valid variable name"),
+            })
+        }
+
+        let value = match value {
+            Value::String( text ) => url_encode(text),
+            other => url_encode(&other.to_string()),
+        };
+        url.push(if index == 0 { '?' } else { '&' });
+        url.push_str(&format!("{name}={value}"));
+    }
+
+    Ok(url)
+}
+
+/// Letters, digits and underscores, not starting with a digit
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Requests which the HTTP endpoints have nothing like, such as live queries
+fn unsupported(method: &RequestMethod) -> SdbError {
+    SdbError::Unsupported {
+        what: format!("{method:?} requests"),
+    }
+}
+
+/// The string parameter at `index`, like the SQL of a `query` or the record
+/// of a `select`
+fn str_param(request: &SurrealRequest, index: usize) -> SdbResult<&str> {
+    match request.params.get(index) {
+        Some( Value::String( param ) ) => Ok(param),
+        _ => Err(SdbError::InvalidRequest {
+            reason: format!("{:?} needs a string as parameter {index}", request.method),
+        }),
+    }
+}
+
+/// The HTTP verb to use with the `/key` endpoint for a request, if it has one.
+/// `patch` has no equivalent, so it's run as SQL instead
+fn key_verb(method: &RequestMethod) -> Option<&'static str> {
+    match method {
+        RequestMethod::Select => Some("GET"),
+        RequestMethod::Create => Some("POST"),
+        RequestMethod::Update => Some("PUT"),
+        RequestMethod::Merge => Some("PATCH"),
+        RequestMethod::Delete => Some("DELETE"),
+        _ => None,
+    }
+}
+
+/// The `/key` endpoint replies with a single statement's results, which are
+/// the records
+fn key_result(replies: Vec<StatementResult>, what: String) -> SdbResult<Value> {
+    let Some( mut reply ) = replies.into_iter().next() else {
+        return Err(SdbError::EmptyResponse { sql: what })
+    };
+    reply.query = Some(what);
+    reply.check()?;
+    Ok(reply.result)
+}
+
+/// The endpoint for a `signin` or `signup` request. The other authentication
+/// requests just change the token sent with each request
+fn auth_endpoint(method: &RequestMethod) -> Option<&'static str> {
+    match method {
+        RequestMethod::Signin => Some("signin"),
+        RequestMethod::Signup => Some("signup"),
+        _ => None,
+    }
+}
+
+/// The `/signin` and `/signup` endpoints reply with a token, or with what
+/// went wrong
+fn auth_result(id: u32, text: &str, what: &str) -> SdbResult<SurrealResponse> {
+    let reply = serde_json::from_str::<Value>(text).map_err(|serde_err| SdbError::QueryResultParseFailure {
+        query: what.to_string(),
+        target_type: "Value".to_string(),
+        serde_err,
+        value: None,
+    })?;
+
+    let code = reply.get("code").and_then(Value::as_i64).unwrap_or(200);
+    if code != 200 {
+        let message = ["information", "description", "details"].iter()
+            .find_map(|key| reply.get(key).and_then(Value::as_str))
+            .unwrap_or("Authentication failed")
+            .to_string();
+        let error = SurrealResponseError { code: code as isize, message };
+        return Ok( SurrealResponse::Error { id, error } )
+    }
+
+    let result = reply.get("token").cloned().unwrap_or_default();
+    Ok( SurrealResponse::Data { id, result } )
+}
+
+#[cfg(test)]
+mod test {
+    use ::serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn vars_are_query_parameters() {
+        let mut info = ServerInfo::new("http://127.0.0.1:8000/test/demo", None, None).unwrap();
+        info.params.insert("limit".to_string(), json!(3));
+
+        let vars = json!({ "title": "A & B", "tags": ["x"] });
+        let url = sql_url(&info, vars.as_object()).unwrap();
+        assert_eq!(url, "http://127.0.0.1:8000/sql?limit=3&tags=%5B%22x%22%5D&title=A%20%26%20B");
+        assert_eq!(sql_url(&info, None).unwrap(), "http://127.0.0.1:8000/sql?limit=3");
+    }
+
+    #[test]
+    fn rejects_bad_var_names() {
+        let info = ServerInfo::new("http://127.0.0.1:8000/test/demo", None, None).unwrap();
+        for name in ["", "1st", "a b", "x;DELETE", "$x"] {
+            let mut vars = Map::new();
+            vars.insert(name.to_string(), json!(1));
+            assert!(matches!(sql_url(&info, Some(&vars)), Err(SdbError::InvalidRequest { .. })), "{name:?}");
+        }
+    }
+}
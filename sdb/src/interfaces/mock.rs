@@ -0,0 +1,293 @@
+use ::std::{
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    sync::{Arc, Mutex},
+};
+use ::regex::Regex;
+
+use crate::{
+    client::interface::*,
+    error::{SdbError, SdbResult},
+    reply::StatementResult,
+    server_info::ServerInfo,
+};
+
+/// An interface which never touches the network. Instead, it's told which SQL
+/// to expect, and what to reply with. For testing code which uses a client
+/// without a server to run against.
+///
+/// Each expectation is met once, by the first request which matches it. Requests
+/// which only change the session, like `use` or `signin`, succeed when nothing
+/// matches them. Any other request fails with [`SdbError::UnexpectedRequest`].
+///
+/// Clones share their expectations, so keep one to check on after building a
+/// client with [`ClientBuilder::build_with`](crate::prelude::ClientBuilder::build_with)
+///
+/// ```rust
+/// # use sdb::prelude::*;
+/// # tokio_test::block_on( async {
+/// let mock = MockInterface::new()
+///     .expect("SELECT * FROM books LIMIT 1", vec![
+///         StatementResult::ok(serde_json::json!([{ "title": "Dune" }])),
+///     ])
+///     .expect_regex(r"^SELECT count\(\) FROM authors", vec![
+///         StatementResult::ok(serde_json::json!([{ "count": 3 }])),
+///     ]);
+///
+/// let client = SurrealClient::open("127.0.0.1:8000/example/demo")
+///     .build_with(mock.clone())
+///     .unwrap();
+///
+/// let mut reply = client.transaction()
+///     .push("SELECT * FROM books LIMIT 1")
+///     .run()
+///     .await
+///     .unwrap();
+///
+/// assert_eq!(reply.next_one::<serde_json::Value>().unwrap()["title"], "Dune");
+/// assert_eq!(mock.unmet().len(), 1);
+/// # });
+/// ```
+///
+/// `select`, `create` and the like are matched by their verb and what they act
+/// on, like `SELECT books:1`. Session requests are matched by their verb and
+/// parameters as JSON, like `SIGNIN {"pass":"root","user":"root"}`. Both reply
+/// with the result of the first statement, or its error
+#[derive(Clone, Default)]
+pub struct MockInterface {
+    expected: Arc<Mutex<Vec<Expectation>>>,
+}
+
+struct Expectation {
+    sql: SqlMatch,
+    replies: Vec<StatementResult>,
+    met: bool,
+}
+
+enum SqlMatch {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl SqlMatch {
+    fn matches(&self, sql: &str) -> bool {
+        match self {
+            SqlMatch::Exact(exact) => exact == sql.trim(),
+            SqlMatch::Pattern(pattern) => pattern.is_match(sql.trim()),
+        }
+    }
+}
+
+impl Display for SqlMatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SqlMatch::Exact(exact) => f.write_str(exact),
+            SqlMatch::Pattern(pattern) => f.write_str(pattern.as_str()),
+        }
+    }
+}
+
+impl MockInterface {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect a request with exactly this SQL, ignoring leading and trailing
+    /// whitespace, and reply to it with one result per statement
+    pub fn expect(self, sql: impl ToString, replies: Vec<StatementResult>) -> Self {
+        let sql = SqlMatch::Exact(sql.to_string().trim().to_string());
+        self.push(sql, replies)
+    }
+
+    /// Expect a request with SQL matching the regular expression `pattern`
+    ///
+    /// ### Panics
+    /// If `pattern` isn't a valid regular expression
+    pub fn expect_regex(self, pattern: &str, replies: Vec<StatementResult>) -> Self {
+        let Ok( pattern ) = Regex::new(pattern) else {
+            panic!("Invalid regular expression for MockInterface: {pattern}")
+        };
+        self.push(SqlMatch::Pattern(pattern), replies)
+    }
+
+    /// The SQL, or patterns, of every expectation which hasn't been met yet
+    pub fn unmet(&self) -> Vec<String> {
+        self.expected.lock().unwrap()
+            .iter()
+            .filter(|exp| !exp.met)
+            .map(|exp| exp.sql.to_string())
+            .collect()
+    }
+
+    /// Panics if any expectation hasn't been met
+    pub fn assert_met(&self) {
+        let unmet = self.unmet();
+        if !unmet.is_empty() {
+            panic!("MockInterface expected SQL which never came:\n\t{}", unmet.join("\n\t"))
+        }
+    }
+
+    fn push(self, sql: SqlMatch, replies: Vec<StatementResult>) -> Self {
+        self.expected.lock().unwrap().push(Expectation {
+            sql,
+            replies,
+            met: false,
+        });
+        self
+    }
+
+    /// Meet the first expectation `sql` matches, and take its replies
+    fn reply_to(&self, sql: &str) -> SdbResult<Vec<StatementResult>> {
+        let mut expected = self.expected.lock().unwrap();
+        let Some( exp ) = expected.iter_mut().find(|exp| !exp.met && exp.sql.matches(sql)) else {
+            return Err(SdbError::UnexpectedRequest { sql: sql.to_string() })
+        };
+
+        exp.met = true;
+        Ok(exp.replies.clone())
+    }
+}
+
+#[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+impl SurrealInterface for MockInterface {
+    async fn execute(
+        &self,
+        _server: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        let id = request.id;
+        let Some( sql ) = request.sql() else {
+            return match self.reply_to(&describe(&request)) {
+                Ok( replies ) => Ok(data_reply(id, replies)),
+                Err( _ ) => Ok(SurrealResponse::Result { id, result: None }),
+            }
+        };
+
+        let replies = self.reply_to(&sql)?;
+        match request.method {
+            RequestMethod::Query => Ok(SurrealResponse::Result {
+                id,
+                result: Some(replies),
+            }),
+            _ => Ok(data_reply(id, replies)),
+        }
+    }
+}
+
+/// What a session request is matched by: its verb, then each parameter as JSON
+fn describe(request: &SurrealRequest) -> String {
+    let mut desc = format!("{:?}", request.method).to_uppercase();
+    for param in &request.params {
+        desc.push(' ');
+        desc.push_str(&param.to_string());
+    }
+    desc
+}
+
+/// The reply to a request which isn't a query: the first result, or its error
+fn data_reply(id: u32, replies: Vec<StatementResult>) -> SurrealResponse {
+    let Some( reply ) = replies.into_iter().next() else {
+        return SurrealResponse::Data { id, result: Default::default() }
+    };
+
+    match reply.status.is_ok() {
+        true => SurrealResponse::Data { id, result: reply.result },
+        false => SurrealResponse::Error {
+            id,
+            error: SurrealResponseError {
+                code: 400,
+                message: reply.detail.unwrap_or_default(),
+            },
+        },
+    }
+}
+
+impl Debug for MockInterface {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("MockInterface")
+            .field("unmet", &self.unmet())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::serde_json::{json, Value};
+
+    use super::*;
+    use crate::prelude::*;
+
+    fn client(mock: &MockInterface) -> SurrealClient {
+        SurrealClient::open("127.0.0.1:8000/test/demo").build_with(mock.clone()).unwrap()
+    }
+
+    fn query(client: &SurrealClient, sql: &str) -> SdbResult<Value> {
+        tokio_test::block_on(async {
+            client.transaction().push(sql).run().await?.next_one::<Value>()
+        })
+    }
+
+    #[test]
+    fn exact_expectations() {
+        let mock = MockInterface::new()
+            .expect("  RETURN 1  ", vec![StatementResult::ok(1)])
+            .expect("RETURN 1", vec![StatementResult::ok(2)]);
+        let client = client(&mock);
+
+        // each is met once, in order, ignoring surrounding whitespace
+        assert_eq!(query(&client, "RETURN 1").unwrap(), json!(1));
+        assert_eq!(query(&client, "\n\tRETURN 1\n").unwrap(), json!(2));
+        assert!(matches!(query(&client, "RETURN 1"), Err(SdbError::UnexpectedRequest { .. })));
+        mock.assert_met();
+    }
+
+    #[test]
+    fn regex_expectations() {
+        let mock = MockInterface::new()
+            .expect_regex(r"^SELECT \* FROM books:\d+$", vec![StatementResult::ok(json!({ "title": "Dune" }))]);
+        let client = client(&mock);
+
+        assert!(matches!(query(&client, "SELECT * FROM books:dune"), Err(SdbError::UnexpectedRequest { .. })));
+        assert_eq!(query(&client, "SELECT * FROM books:1").unwrap()["title"], "Dune");
+        mock.assert_met();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid regular expression")]
+    fn invalid_regex_panics() {
+        let _ = MockInterface::new().expect_regex("(unclosed", vec![]);
+    }
+
+    #[test]
+    fn unmet_expectations_are_reported() {
+        let mock = MockInterface::new()
+            .expect("RETURN 1", vec![StatementResult::ok(1)])
+            .expect_regex("^RETURN [0-9]+$", vec![StatementResult::ok(2)]);
+        assert_eq!(mock.unmet(), vec!["RETURN 1", "^RETURN [0-9]+$"]);
+
+        query(&client(&mock), "RETURN 1").unwrap();
+        assert_eq!(mock.unmet(), vec!["^RETURN [0-9]+$"]);
+
+        let report = std::panic::catch_unwind(|| mock.assert_met()).unwrap_err();
+        let report = report.downcast_ref::<String>().unwrap();
+        assert!(report.contains("never came"));
+        assert!(report.contains("^RETURN [0-9]+$"));
+    }
+
+    #[test]
+    fn build_with_uses_the_interface() {
+        let mock = MockInterface::new()
+            .expect("SELECT books", vec![StatementResult::ok(json!([{ "id": "books:1" }]))]);
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo")
+            .timeout(std::time::Duration::from_secs(1))
+            .build_with(mock.clone())
+            .unwrap();
+
+        let result = tokio_test::block_on(client.run_request(SurrealRequest::select(&Resource::from("books"))));
+        assert!(matches!(result, Ok(SurrealResponse::Data { .. })));
+        mock.assert_met();
+
+        let result = SurrealClient::open("ftp://127.0.0.1").build_with(MockInterface::new());
+        assert!(matches!(result, Err(SdbError::InvalidHostString { .. })));
+    }
+}
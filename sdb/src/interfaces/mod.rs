@@ -7,3 +7,13 @@ pub use http::*;
 mod ws;
 #[cfg(feature = "ws")]
 pub use ws::*;
+
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "mock")]
+pub use mock::*;
+
+#[cfg(feature = "mock")]
+mod cassette;
+#[cfg(feature = "mock")]
+pub use cassette::*;
@@ -1,3 +1,5 @@
+mod router;
+
 #[cfg(all(feature = "ws", target_family = "wasm"))]
 mod ws_wasm;
 #[cfg(all(feature = "ws", target_family = "wasm"))]
@@ -0,0 +1,258 @@
+use ::std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+use ::futures::channel::{mpsc, oneshot};
+use ::serde_json::Value;
+
+use crate::{
+    client::{interface::SurrealResponse, LiveReceiver},
+    error::{SdbError, SdbResult},
+};
+
+type Waiter = oneshot::Sender<SdbResult<SurrealResponse>>;
+
+/// How many notifications to hold on to for live queries nobody has subscribed
+/// to yet. They can arrive before the reply which says the query's id
+const MAX_ORPHANS: usize = 64;
+
+/// Matches replies read off of a websocket with the requests waiting on them,
+/// using [`SurrealRequest::id`](crate::prelude::SurrealRequest). This is what lets
+/// many requests share one socket at the same time.
+#[derive(Clone, Default)]
+pub(crate) struct ResponseRouter {
+    pending: Arc<Mutex<HashMap<u32, Waiter>>>,
+    live: Arc<Mutex<LiveRoutes>>,
+}
+
+#[derive(Default)]
+struct LiveRoutes {
+    subscribers: HashMap<String, mpsc::UnboundedSender<SdbResult<Value>>>,
+    orphans: VecDeque<(String, Value)>,
+}
+
+impl ResponseRouter {
+    /// Start waiting for the reply to request `id`. Must be called before the
+    /// request is sent, or the reply might arrive first and be dropped.
+    pub fn register(&self, id: u32) -> oneshot::Receiver<SdbResult<SurrealResponse>> {
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        receiver
+    }
+
+    /// Stop waiting for the reply to request `id`, usually because sending it failed
+    pub fn forget(&self, id: u32) {
+        self.pending.lock().unwrap().remove(&id);
+    }
+
+    /// Start receiving the notifications of live query `live_id`
+    pub fn subscribe(&self, live_id: &str) -> LiveReceiver {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut live = self.live.lock().unwrap();
+
+        let (early, rest) = std::mem::take(&mut live.orphans)
+            .into_iter()
+            .partition::<VecDeque<_>, _>(|(id, _)| id == live_id);
+        live.orphans = rest;
+        for (_, note) in early {
+            let _ = sender.unbounded_send(Ok(note));
+        }
+
+        live.subscribers.insert(live_id.to_string(), sender);
+        receiver
+    }
+
+    /// Stop receiving the notifications of live query `live_id`
+    pub fn unsubscribe(&self, live_id: &str) {
+        self.live.lock().unwrap().subscribers.remove(live_id);
+    }
+
+    /// Hand a text payload from the socket to whoever is waiting on it
+    pub fn route(&self, payload: &str) {
+        let parsed = serde_json::from_str::<SurrealResponse>(payload);
+        let id = match &parsed {
+            Ok(response) => response.id(),
+            Err(_) => match reply_id(payload) {
+                Some(id) => id,
+                None => {
+                    if !self.route_notification(payload) {
+                        #[cfg(feature = "log")]
+                        log::warn!("Unroutable websocket message: {payload}");
+                    }
+                    return;
+                }
+            },
+        };
+
+        let Some(waiter) = self.pending.lock().unwrap().remove(&id) else {
+            #[cfg(feature = "log")]
+            log::warn!("Recieved a reply for request {id}, but nothing is waiting for it");
+            return;
+        };
+
+        let result = parsed.map_err(|serde_err| SdbError::QueryResultParseFailure {
+            query: String::new(),
+            target_type: "SurrealResponse".to_string(),
+            serde_err,
+            value: serde_json::from_str(payload).ok(),
+        });
+
+        // the caller may have given up waiting, which is fine
+        let _ = waiter.send(result);
+    }
+
+    /// Live query notifications have no request id, just the id of the live
+    /// query in their `result`. Returns false if this isn't one
+    fn route_notification(&self, payload: &str) -> bool {
+        let Ok( Value::Object( mut msg ) ) = serde_json::from_str::<Value>(payload) else { return false };
+        let Some( note ) = msg.remove("result") else { return false };
+        let Some( live_id ) = note.get("id").and_then(Value::as_str).map(str::to_string) else { return false };
+        if note.get("action").is_none() {
+            return false;
+        }
+
+        let mut live = self.live.lock().unwrap();
+        match live.subscribers.get(&live_id) {
+            Some(sender) => {
+                if sender.unbounded_send(Ok(note)).is_err() {
+                    live.subscribers.remove(&live_id);
+                }
+            }
+            None => {
+                if live.orphans.len() >= MAX_ORPHANS {
+                    live.orphans.pop_front();
+                }
+                live.orphans.push_back((live_id, note));
+            }
+        }
+        true
+    }
+
+    /// The socket is gone, so fail every request still waiting on a reply, and
+    /// end every live query, since the server forgets them too
+    pub fn close_all(&self, url: &str, info: &str) {
+        let closed = || SdbError::ConnectionClosed {
+            info: info.to_string(),
+            url: url.to_string(),
+        };
+
+        let waiters = std::mem::take(&mut *self.pending.lock().unwrap());
+        for (_, waiter) in waiters {
+            let _ = waiter.send(Err(closed()));
+        }
+
+        let mut live = self.live.lock().unwrap();
+        live.orphans.clear();
+        for (_, sender) in live.subscribers.drain() {
+            let _ = sender.unbounded_send(Err(closed()));
+        }
+    }
+}
+
+/// Wait for a reply registered with [`ResponseRouter::register`]
+pub(crate) async fn wait_for(
+    receiver: oneshot::Receiver<SdbResult<SurrealResponse>>,
+    url: &str,
+) -> SdbResult<SurrealResponse> {
+    match receiver.await {
+        Ok(response) => response,
+        Err(oneshot::Canceled) => Err(SdbError::ConnectionClosed {
+            info: "Socket closed before a reply was recieved".to_string(),
+            url: url.to_string(),
+        }),
+    }
+}
+
+fn reply_id(payload: &str) -> Option<u32> {
+    let value = serde_json::from_str::<Value>(payload).ok()?;
+    let id = value.get("id")?.as_u64()?;
+    u32::try_from(id).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use ::futures::{FutureExt, StreamExt};
+
+    use super::*;
+
+    fn reply(id: u32, value: &str) -> String {
+        format!(r#"{{"id":{id},"result":[{{"time":"1ms","status":"OK","result":"{value}"}}]}}"#)
+    }
+
+    fn value_of(response: SurrealResponse) -> Value {
+        let SurrealResponse::Result { result: Some( mut stmts ), .. } = response else {
+            panic!("Expected statement results, got {response:?}")
+        };
+        stmts.remove(0).result
+    }
+
+    #[test]
+    fn routes_replies_by_id() {
+        let router = ResponseRouter::default();
+        let mut first = router.register(1);
+        let mut second = router.register(2);
+
+        // replies can arrive in any order
+        router.route(&reply(2, "two"));
+        router.route(&reply(1, "one"));
+
+        let first = first.try_recv().unwrap().unwrap().unwrap();
+        let second = second.try_recv().unwrap().unwrap().unwrap();
+        assert_eq!(first.id(), 1);
+        assert_eq!(value_of(first), "one");
+        assert_eq!(second.id(), 2);
+        assert_eq!(value_of(second), "two");
+    }
+
+    #[test]
+    fn ignores_unknown_ids() {
+        let router = ResponseRouter::default();
+        let mut waiting = router.register(1);
+
+        router.route(&reply(7, "stray"));
+        assert!(waiting.try_recv().unwrap().is_none());
+
+        router.forget(1);
+        router.route(&reply(1, "late"));
+        assert!(waiting.try_recv().is_err());
+    }
+
+    #[test]
+    fn unparsable_reply_fails_its_request() {
+        let router = ResponseRouter::default();
+        let mut waiting = router.register(3);
+
+        router.route(r#"{"id":3,"result":[{"time":"1ms","status":"OK"}]}"#);
+        let result = waiting.try_recv().unwrap().unwrap();
+        assert!(matches!(result, Err(SdbError::QueryResultParseFailure { .. })));
+    }
+
+    #[test]
+    fn routes_live_notifications() {
+        let router = ResponseRouter::default();
+        let note = |live_id: &str| format!(r#"{{"result":{{"id":"{live_id}","action":"CREATE","result":{{}}}}}}"#);
+
+        // arrives before anyone subscribes
+        router.route(&note("early"));
+        let mut early = router.subscribe("early");
+        let mut later = router.subscribe("later");
+        router.route(&note("later"));
+
+        assert_eq!(early.next().now_or_never().unwrap().unwrap().unwrap()["id"], "early");
+        assert_eq!(later.next().now_or_never().unwrap().unwrap().unwrap()["id"], "later");
+        assert!(early.next().now_or_never().is_none());
+    }
+
+    #[test]
+    fn close_all_fails_everything_waiting() {
+        let router = ResponseRouter::default();
+        let mut waiting = router.register(1);
+        let mut live = router.subscribe("abc");
+
+        router.close_all("ws://localhost:8000", "gone");
+        let result = waiting.try_recv().unwrap().unwrap();
+        assert!(matches!(result, Err(SdbError::ConnectionClosed { .. })));
+        let note = live.next().now_or_never().unwrap().unwrap();
+        assert!(matches!(note, Err(SdbError::ConnectionClosed { .. })));
+    }
+}
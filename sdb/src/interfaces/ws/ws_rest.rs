@@ -1,16 +1,42 @@
-use ::std::fmt::Debug;
-use ::websockets::{Frame, WebSocket, WebSocketBuilder, WebSocketError};
+use ::std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+};
+use ::futures::lock::Mutex;
+use ::websockets::{Frame, WebSocket, WebSocketBuilder, WebSocketError, WebSocketReadHalf, WebSocketWriteHalf};
 
 use crate::{
-    client::interface::*,
+    client::{interface::*, LiveReceiver},
+    deadline::Deadline,
     error::{SdbError, SdbResult},
+    reconnect::{self, ReconnectEvent, ReconnectListener, ReconnectPolicy},
+    retry,
     server_info::ServerInfo,
 };
 
+use super::router::{wait_for, ResponseRouter};
+
+/// Talks to SurrealDB over a single websocket. Any number of requests can be
+/// in flight at once; a background task reads replies and hands each one to
+/// the caller waiting on its id.
 pub struct WSSurrealInterface {
+    state: Mutex<SocketState>,
+    router: ResponseRouter,
+    reconnect: ReconnectPolicy,
+    listener: Option<ReconnectListener>,
+    /// Live queries dropped since the last request, to be killed by the next one
+    kills: StdMutex<Vec<String>>,
+}
+
+struct SocketState {
+    builder: WebSocketBuilder,
+    writer: Option<WebSocketWriteHalf>,
+    /// Set by the reader task once the socket stops producing frames
+    closed: Arc<AtomicBool>,
     connected_to: Option<ServerInfo>,
-    builder: Option<WebSocketBuilder>,
-    socket: Option<WebSocket>,
 }
 
 impl SurrealInterfaceBuilder for WSSurrealInterface {
@@ -24,83 +50,193 @@ impl SurrealInterfaceBuilder for WSSurrealInterface {
             builder.add_header(&k, &v);
         }
 
+        #[cfg(feature = "tls")]
+        if server.protocol.is_secure() {
+            server.tls.apply_websockets(&mut builder)?;
+        }
+
         Ok(Self {
-            connected_to: None,
-            builder: Some(builder),
-            socket: None,
+            state: Mutex::new(SocketState {
+                builder,
+                writer: None,
+                closed: Arc::new(AtomicBool::new(false)),
+                connected_to: None,
+            }),
+            router: ResponseRouter::default(),
+            reconnect: ReconnectPolicy::default(),
+            listener: None,
+            kills: StdMutex::new(Vec::new()),
         })
     }
 }
 
 impl WSSurrealInterface {
-    async fn ensure_connected(&mut self, server: &ServerInfo) -> SdbResult<()> {
+    /// Set how, and if, to reconnect after the socket drops
+    pub fn with_reconnect(
+        mut self,
+        policy: ReconnectPolicy,
+        listener: Option<ReconnectListener>,
+    ) -> Self {
+        self.reconnect = policy;
+        self.listener = listener;
+        self
+    }
+
+    /// Only the waiting is cut short by the `deadline`, never the writing. If it
+    /// passes mid-handshake, the next request starts the handshake over
+    async fn ensure_connected(&self, state: &mut SocketState, server: &ServerInfo, deadline: &mut Deadline) -> SdbResult<()> {
+        // The socket dropped, so reconnect and replay the session
+        if state.closed.load(Ordering::Acquire) {
+            state.writer = None;
+            state.closed = Arc::new(AtomicBool::new(false));
+            // the server forgot every live query along with the old socket
+            self.kills.lock().unwrap().clear();
+            deadline.run(self.reconnect(state, server)).await?;
+        }
+
         // Connect to socket if not already connected
-        match &mut self.builder {
-            Some(builder) => {
-                let url = server.full_url();
-                let socket = convert_err(builder.connect(&url).await, server)?;
-                self.builder = None;
-                self.socket = Some(socket);
+        if state.writer.is_none() {
+            deadline.run(self.connect(state, server)).await?;
+        }
+
+        // Handshake, or rehandshake, with the server
+        if state.connected_to.as_ref() != Some(server) {
+            for req in SurrealRequest::handshake(server) {
+                let method = format!("{:?}", req.method);
+                let receiver = self.send(state, server, req).await?;
+                if let SurrealResponse::Error { error, .. } = deadline.run(wait_for(receiver, &server.full_url())).await? {
+                    return Err(SdbError::from_response(method, error));
+                }
             }
-            None => {}
+            state.connected_to = Some(server.clone());
         }
 
-        // Handshake, or rehandshake, with
-        if self.connected_to.is_none() || self.connected_to.as_ref().unwrap().ne(server) {
-            let socket = self.socket.as_mut().unwrap(); // else { panic!() };
-            let req = SurrealRequest::new_auth(server);
-            let txt = serde_json::to_string(&req).unwrap();
-            socket.send(Frame::text(txt)).await.unwrap();
-            recieve_next(socket).await;
-            self.connected_to = Some(server.clone());
-
-            Ok(())
-        } else {
-            Ok(())
+        Ok(())
+    }
+
+    async fn connect(&self, state: &mut SocketState, server: &ServerInfo) -> SdbResult<()> {
+        let url = server.full_url();
+        let socket = convert_err(state.builder.connect(&url).await, server)?;
+        let (reader, writer) = socket.split();
+
+        tokio::spawn(read_loop(reader, self.router.clone(), state.closed.clone(), url));
+        state.writer = Some(writer);
+        state.connected_to = None;
+        Ok(())
+    }
+
+    /// Keep trying to connect, backing off between attempts, until it works or
+    /// the [`ReconnectPolicy`] gives up
+    async fn reconnect(&self, state: &mut SocketState, server: &ServerInfo) -> SdbResult<()> {
+        let url = server.full_url();
+        let mut last_err = SdbError::ConnectionClosed {
+            url: url.clone(),
+            info: "Socket closed".to_string(),
+        };
+
+        let mut attempt = 0;
+        while self.reconnect.allows(attempt) {
+            if attempt > 0 {
+                let delay = self.reconnect.delay(attempt);
+                reconnect::emit(&self.listener, ReconnectEvent::Retrying {
+                    url: url.clone(),
+                    attempt,
+                    delay,
+                    reason: last_err.to_string(),
+                });
+                retry::sleep(delay).await;
+            }
+
+            attempt += 1;
+            match self.connect(state, server).await {
+                Ok(()) => {
+                    reconnect::emit(&self.listener, ReconnectEvent::Reconnected { url, attempts: attempt });
+                    return Ok(());
+                }
+                Err(err) => last_err = err,
+            }
         }
+
+        reconnect::emit(&self.listener, ReconnectEvent::GaveUp {
+            url,
+            attempts: attempt,
+            reason: last_err.to_string(),
+        });
+        Err(last_err)
     }
-}
 
-unsafe impl Send for WSSurrealInterface {}
-unsafe impl Sync for WSSurrealInterface {}
+    /// Kill the live queries which were dropped. Nothing is waiting on the replies
+    async fn send_kills(&self, state: &mut SocketState, server: &ServerInfo) -> SdbResult<()> {
+        let kills = std::mem::take(&mut *self.kills.lock().unwrap());
+        for live_id in kills {
+            // the reply is routed nowhere once the receiver is dropped
+            drop(self.send(state, server, SurrealRequest::kill(&live_id)).await?);
+        }
+        Ok(())
+    }
 
-#[async_trait::async_trait(?Send)]
-impl SurrealInterface for WSSurrealInterface {
-    async fn execute(
-        &mut self,
+    /// Sends a request and returns the receiver its reply will be routed to
+    async fn send(
+        &self,
+        state: &mut SocketState,
         server: &ServerInfo,
         request: SurrealRequest,
-    ) -> SdbResult<SurrealResponse> {
-        self.ensure_connected(server).await?;
-
-        let socket = self.socket.as_mut().unwrap();
+    ) -> SdbResult<futures::channel::oneshot::Receiver<SdbResult<SurrealResponse>>> {
+        let Some(writer) = state.writer.as_mut() else {
+            return Err(SdbError::ConnectionClosed {
+                url: server.full_url(),
+                info: "Socket not connected".to_string(),
+            });
+        };
 
-        socket.send_text(request.stringify()).await.unwrap();
+        let receiver = self.router.register(request.id);
+        if let Err(err) = convert_err(writer.send_text(request.stringify()).await, server) {
+            self.router.forget(request.id);
+            return Err(err);
+        }
 
-        let frame = recieve_next(socket).await;
+        Ok(receiver)
+    }
+}
 
-        let Frame::Text { payload, .. } = frame else {
-            panic!("Recieved a Binary payload - WSSurrealInterface::send")
-        };
+#[async_trait::async_trait]
+impl SurrealInterface for WSSurrealInterface {
+    async fn execute(
+        &self,
+        server: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<SurrealResponse> {
+        let mut deadline = Deadline::after(request.timeout);
+        let req_id = request.id;
 
-        let response = match serde_json::from_str::<SurrealResponse>(&payload) {
-            Ok(r) => r,
-            Err(err) => {
-                println!("{payload}\n");
-                Err(err).unwrap()
-            }
+        // only hold the lock for as long as it takes to send, so other
+        // requests can go out while this one waits for its reply
+        let receiver = {
+            let mut state = deadline.run(async { Ok::<_, SdbError>(self.state.lock().await) }).await?;
+            self.ensure_connected(&mut state, server, &mut deadline).await?;
+            self.send_kills(&mut state, server).await?;
+            self.send(&mut state, server, request).await?
         };
 
-        if !response.is_for(&request) {
-            println!(
-                "\n\n{payload}\n\n{:?} =/= {:?}\n\n",
-                request.id,
-                response.id()
-            );
-            unreachable!("Responses recieved out of order :( ");
+        let response = deadline.run(wait_for(receiver, &server.full_url())).await;
+        if let Err(SdbError::NetworkTimeout) = response {
+            // the reply may still come, and nothing should be waiting for it
+            self.router.forget(req_id);
         }
+        response
+    }
 
-        Ok(response)
+    fn subscribe(&self, live_id: &str) -> SdbResult<LiveReceiver> {
+        Ok(self.router.subscribe(live_id))
+    }
+
+    fn unsubscribe(&self, live_id: &str) {
+        self.router.unsubscribe(live_id);
+    }
+
+    fn kill_live(&self, live_id: &str) {
+        self.router.unsubscribe(live_id);
+        self.kills.lock().unwrap().push(live_id.to_string());
     }
 }
 
@@ -110,15 +246,24 @@ impl Debug for WSSurrealInterface {
     }
 }
 
-async fn recieve_next(sock: &mut WebSocket) -> Frame {
-    loop {
-        let reply = sock.receive().await.unwrap();
-        if let Frame::Text { .. } = reply {
-            return reply;
-        } else if let Frame::Binary { .. } = reply {
-            return reply;
+/// Reads frames until the socket closes, routing each reply to its caller
+async fn read_loop(
+    mut reader: WebSocketReadHalf,
+    router: ResponseRouter,
+    closed: Arc<AtomicBool>,
+    url: String,
+) {
+    let info = loop {
+        match reader.receive().await {
+            Ok(Frame::Text { payload, .. }) => router.route(&payload),
+            Ok(Frame::Close { .. }) => break "Socket closed by server".to_string(),
+            Ok(_) => continue,
+            Err(err) => break format!("{err:?}"),
         }
-    }
+    };
+
+    closed.store(true, Ordering::Release);
+    router.close_all(&url, &info);
 }
 
 /// Convert [`WebSocketError`] to [`SdbError`],
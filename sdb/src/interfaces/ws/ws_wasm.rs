@@ -1,11 +1,28 @@
-use ::futures::{SinkExt, StreamExt};
-use ::gloo_net::websocket::{futures::WebSocket, Message};
-use ::serde_json::from_str;
-use ::std::fmt::Debug;
+use ::futures::{
+    channel::oneshot,
+    lock::Mutex,
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use ::gloo_net::websocket::{futures::WebSocket, Message, State};
+use ::std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+};
 
-// use wasm_sockets::{Message, ConnectionStatus, PollingClient};
+use crate::{
+    client::{interface::*, LiveReceiver},
+    deadline::Deadline,
+    error::*,
+    reconnect::{self, ReconnectEvent, ReconnectListener, ReconnectPolicy},
+    retry,
+    server_info::ServerInfo,
+};
 
-use crate::{client::interface::*, error::*, server_info::ServerInfo};
+use super::router::{wait_for, ResponseRouter};
 
 impl Debug for WSSurrealInterface {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -13,76 +30,238 @@ impl Debug for WSSurrealInterface {
     }
 }
 
+/// Talks to SurrealDB over a single browser websocket. Any number of requests
+/// can be in flight at once; a background task reads replies and hands each one
+/// to the caller waiting on its id.
 pub struct WSSurrealInterface {
-    socket: Option<WebSocket>,
+    state: Mutex<SocketState>,
+    router: ResponseRouter,
+    reconnect: ReconnectPolicy,
+    listener: Option<ReconnectListener>,
+    /// Live queries dropped since the last request, to be killed by the next one
+    kills: StdMutex<Vec<String>>,
+}
+
+struct SocketState {
+    writer: Option<SplitSink<WebSocket, Message>>,
+    /// Set by the reader task once the socket stops producing messages
+    closed: Arc<AtomicBool>,
+    connected_to: Option<ServerInfo>,
 }
 
 impl WSSurrealInterface {
-    async fn ensure_connected(&mut self, info: &ServerInfo) -> SdbResult<()> {
-        if self.socket.is_none() {
-            let socket = WebSocket::open(&info.full_url()).unwrap();
-            self.socket = Some(socket);
+    /// Set how, and if, to reconnect after the socket drops
+    pub fn with_reconnect(
+        mut self,
+        policy: ReconnectPolicy,
+        listener: Option<ReconnectListener>,
+    ) -> Self {
+        self.reconnect = policy;
+        self.listener = listener;
+        self
+    }
+
+    /// Only the waiting is cut short by the `deadline`, never the writing. If it
+    /// passes mid-handshake, the next request starts the handshake over
+    async fn ensure_connected(&self, state: &mut SocketState, info: &ServerInfo, deadline: &mut Deadline) -> SdbResult<()> {
+        let url = info.full_url();
+
+        if state.closed.load(Ordering::Acquire) {
+            state.writer = None;
+            state.closed = Arc::new(AtomicBool::new(false));
+            // the server forgot every live query along with the old socket
+            self.kills.lock().unwrap().clear();
+            deadline.run(self.reconnect(state, info)).await?;
+        }
+
+        if state.writer.is_none() {
+            deadline.run(self.connect(state, info)).await?;
+        }
+
+        if state.connected_to.as_ref() != Some(info) {
+            for req in SurrealRequest::handshake(info) {
+                let method = format!("{:?}", req.method);
+                let receiver = self.send(state, info, req).await?;
+                if let SurrealResponse::Error { error, .. } = deadline.run(wait_for(receiver, &url)).await? {
+                    return Err(SdbError::from_response(method, error));
+                }
+            }
+            state.connected_to = Some(info.clone());
+        }
+
+        Ok(())
+    }
+
+    async fn connect(&self, state: &mut SocketState, info: &ServerInfo) -> SdbResult<()> {
+        let url = info.full_url();
+        let Ok( mut socket ) = WebSocket::open(&url) else {
+            return Err(SdbError::ConnectionRefused { url })
+        };
+
+        // opening only starts the handshake, and a server which is down shows up
+        // later as an error event. The socket is ready once either has happened
+        let _ = futures::future::poll_fn(|cx| socket.poll_ready_unpin(cx)).await;
+        if !matches!(socket.state(), State::Open) {
+            return Err(SdbError::ConnectionRefused { url })
         }
+        let (writer, reader) = socket.split();
 
-        if let Some(socket) = &mut self.socket {
-            let req = SurrealRequest::use_ns_db(&info.namespace, &info.database);
-            let msg = req.stringify();
-            socket.send(Message::Text(msg)).await.unwrap();
-            if info.auth.is_some() {
-                let req = SurrealRequest::new_auth(&info);
-                let msg = req.stringify();
-                socket.send(Message::Text(msg)).await.unwrap();
-                let _ = socket.next().await.unwrap().unwrap();
+        wasm_bindgen_futures::spawn_local(read_loop(
+            reader,
+            self.router.clone(),
+            state.closed.clone(),
+            url,
+        ));
+        state.writer = Some(writer);
+        state.connected_to = None;
+        Ok(())
+    }
+
+    /// Keep trying to connect, backing off between attempts, until it works or
+    /// the [`ReconnectPolicy`] gives up
+    async fn reconnect(&self, state: &mut SocketState, info: &ServerInfo) -> SdbResult<()> {
+        let url = info.full_url();
+        let mut last_err = SdbError::ConnectionClosed {
+            url: url.clone(),
+            info: "Socket closed".to_string(),
+        };
+
+        let mut attempt = 0;
+        while self.reconnect.allows(attempt) {
+            if attempt > 0 {
+                let delay = self.reconnect.delay(attempt);
+                reconnect::emit(&self.listener, ReconnectEvent::Retrying {
+                    url: url.clone(),
+                    attempt,
+                    delay,
+                    reason: last_err.to_string(),
+                });
+                retry::sleep(delay).await;
             }
 
-            let _ = socket.next().await.unwrap();
-            // let _reply = socket.next().await.unwrap().unwrap();
+            attempt += 1;
+            match self.connect(state, info).await {
+                Ok(()) => {
+                    reconnect::emit(&self.listener, ReconnectEvent::Reconnected { url, attempts: attempt });
+                    return Ok(());
+                }
+                Err(err) => last_err = err,
+            }
         }
 
+        reconnect::emit(&self.listener, ReconnectEvent::GaveUp {
+            url,
+            attempts: attempt,
+            reason: last_err.to_string(),
+        });
+        Err(last_err)
+    }
+
+    /// Kill the live queries which were dropped. Nothing is waiting on the replies
+    async fn send_kills(&self, state: &mut SocketState, info: &ServerInfo) -> SdbResult<()> {
+        let kills = std::mem::take(&mut *self.kills.lock().unwrap());
+        for live_id in kills {
+            // the reply is routed nowhere once the receiver is dropped
+            drop(self.send(state, info, SurrealRequest::kill(&live_id)).await?);
+        }
         Ok(())
     }
+
+    /// Sends a request and returns the receiver its reply will be routed to
+    async fn send(
+        &self,
+        state: &mut SocketState,
+        info: &ServerInfo,
+        request: SurrealRequest,
+    ) -> SdbResult<oneshot::Receiver<SdbResult<SurrealResponse>>> {
+        let Some(writer) = state.writer.as_mut() else {
+            return Err(SdbError::ConnectionClosed {
+                url: info.full_url(),
+                info: "Socket not connected".to_string(),
+            });
+        };
+
+        let receiver = self.router.register(request.id);
+        if let Err(err) = writer.send(Message::Text(request.stringify())).await {
+            self.router.forget(request.id);
+            return Err(SdbError::WebsocketNetworkError(err));
+        }
+
+        Ok(receiver)
+    }
 }
 
 impl SurrealInterfaceBuilder for WSSurrealInterface {
     fn new(_info: &ServerInfo) -> SdbResult<Self> {
-        Ok(Self { socket: None })
+        Ok(Self {
+            state: Mutex::new(SocketState {
+                writer: None,
+                closed: Arc::new(AtomicBool::new(false)),
+                connected_to: None,
+            }),
+            router: ResponseRouter::default(),
+            reconnect: ReconnectPolicy::default(),
+            listener: None,
+            kills: StdMutex::new(Vec::new()),
+        })
     }
 }
 
-unsafe impl Send for WSSurrealInterface {}
-unsafe impl Sync for WSSurrealInterface {}
-
 #[async_trait::async_trait(?Send)]
 impl SurrealInterface for WSSurrealInterface {
     async fn execute(
-        &mut self,
+        &self,
         info: &ServerInfo,
         request: SurrealRequest,
     ) -> SdbResult<SurrealResponse> {
-        self.ensure_connected(info).await?;
-        let msg = request.stringify();
+        let mut deadline = Deadline::after(request.timeout);
+        let req_id = request.id;
 
-        let msg = Message::Text(msg);
-        let socket = self.socket.as_mut().unwrap(); // else { panic!("Socket to be initialized") };
+        let receiver = {
+            let mut state = deadline.run(async { Ok::<_, SdbError>(self.state.lock().await) }).await?;
+            self.ensure_connected(&mut state, info, &mut deadline).await?;
+            self.send_kills(&mut state, info).await?;
+            self.send(&mut state, info, request).await?
+        };
 
-        let _ = socket.send(msg).await;
-        let reply = socket.next().await.unwrap().unwrap();
+        let response = deadline.run(wait_for(receiver, &info.full_url())).await;
+        if let Err(SdbError::NetworkTimeout) = response {
+            // the reply may still come, and nothing should be waiting for it
+            self.router.forget(req_id);
+        }
+        response
+    }
 
-        let Message::Text( txt ) = reply else { panic!() };
+    fn subscribe(&self, live_id: &str) -> SdbResult<LiveReceiver> {
+        Ok(self.router.subscribe(live_id))
+    }
 
-        match from_str::<SurrealResponse>(&txt) {
-            Ok(result) if !result.is_for(&request) => {
-                unimplemented!("Multi-query routing")
-            }
-            Ok(r) => Ok(r),
-            Err(err) => Err(SdbError::QueryResultParseFailure {
-                query: request.params[0].to_string(),
-                target_type: "SurrealResponse".to_string(),
-                serde_err: err,
-                value: None,
-            }),
-        }
+    fn unsubscribe(&self, live_id: &str) {
+        self.router.unsubscribe(live_id);
     }
 
-    
+    fn kill_live(&self, live_id: &str) {
+        self.router.unsubscribe(live_id);
+        self.kills.lock().unwrap().push(live_id.to_string());
+    }
+}
+
+/// Reads messages until the socket closes, routing each reply to its caller
+async fn read_loop(
+    mut reader: SplitStream<WebSocket>,
+    router: ResponseRouter,
+    closed: Arc<AtomicBool>,
+    url: String,
+) {
+    let info = loop {
+        match reader.next().await {
+            Some(Ok(Message::Text(txt))) => router.route(&txt),
+            Some(Ok(Message::Bytes(_))) => continue,
+            Some(Err(err)) => break format!("{err:?}"),
+            None => break "Socket closed by server".to_string(),
+        }
+    };
+
+    closed.store(true, Ordering::Release);
+    router.close_all(&url, &info);
 }
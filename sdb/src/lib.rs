@@ -3,31 +3,58 @@
 pub use ::sdb_macros::*;
 pub use ::serde_json::Value;
 
+#[cfg(all(feature = "blocking", not(target_family = "wasm")))]
+pub mod blocking;
 mod client;
 mod interfaces;
 mod reply;
 mod transaction;
 
 mod credentials;
+#[cfg(any(feature = "ws", feature = "http"))]
+mod deadline;
 mod error;
 mod protocol;
+#[cfg(all(feature = "pool", not(target_family = "wasm")))]
+mod pool;
+mod reconnect;
+mod retry;
 mod record;
 mod server_info;
+mod tls;
 
 
 pub mod prelude {
     pub use sdb_macros::SurrealRecord;
     pub use crate::{
-        client::interface::{SurrealRequest, SurrealResponse, SurrealResponseError, RequestMethod},
-        client::SurrealClient,
+        client::interface::{Layer, SurrealInterface, SurrealRequest, SurrealResponse, SurrealResponseError, RequestMethod},
+        client::{ClientBuilder, SurrealClient},
         credentials::Credentials,
         error::{SdbError, SdbResult},
         protocol::Protocol,
+        reconnect::{ReconnectEvent, ReconnectListener, ReconnectPolicy},
         record::*,
-        reply::QueryReply,
+        reply::{FromReply, FromStatement, QueryReply, QueryStats, StatementResult, StatementStats, StatementStatus},
+        retry::RetryPolicy,
         server_info::ServerInfo,
+        tls::TlsConfig,
         transaction::TransactionBuilder,
     };
+
+    #[allow(deprecated)]
+    pub use crate::client::UpdateMode;
+
+    #[cfg(feature = "ws")]
+    pub use crate::client::{LiveEvent, LiveQuery};
+
+    #[cfg(feature = "log")]
+    pub use crate::client::interface::TracingLayer;
+
+    #[cfg(feature = "mock")]
+    pub use crate::interfaces::{MockInterface, RecordingInterface, ReplayInterface};
+
+    #[cfg(all(feature = "pool", not(target_family = "wasm")))]
+    pub use crate::pool::{PoolConfig, PooledClient, SurrealPool};
 }
 
 
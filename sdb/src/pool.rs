@@ -0,0 +1,337 @@
+use ::std::{
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use ::tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    client::{ClientBuilder, SurrealClient, SurrealInterface},
+    error::{SdbError, SdbResult},
+    server_info::ServerInfo,
+};
+
+/// How many connections a [`SurrealPool`] keeps, and for how long
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolConfig {
+    /// Connections opened when the pool is built, and never closed for being idle
+    pub min_connections: usize,
+    /// The most connections checked out at once. [`SurrealPool::get`] waits for one
+    /// to be returned beyond this
+    pub max_connections: usize,
+    /// Close connections which haven't been used for this long. `None` keeps them forever.
+    ///
+    /// There's no background task doing this, timed out connections are only
+    /// closed when a client is checked out
+    pub idle_timeout: Option<Duration>,
+    /// How long [`SurrealPool::get`] waits for a connection to be returned before
+    /// failing with [`SdbError::PoolTimeout`]. `None` waits forever
+    pub checkout_timeout: Option<Duration>,
+    /// Make sure idle connections still work before handing them out. This costs
+    /// a round trip to the server on every checkout
+    pub health_check: bool,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            min_connections: 1,
+            max_connections: 10,
+            idle_timeout: Some(Duration::from_secs(300)),
+            checkout_timeout: None,
+            health_check: false,
+        }
+    }
+}
+
+/// A set of [`SurrealClient`]s, each with its own connection, that are handed out
+/// one caller at a time. Clone it freely, every clone shares the same connections.
+///
+/// Built with [`ClientBuilder::build_pool`]. Not available in `wasm`.
+///
+/// ### Example
+/// ```rust
+/// # use sdb::prelude::*;
+/// # tokio_test::block_on( async {
+/// let pool = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo")
+///     .build_pool(PoolConfig {
+///         max_connections: 4,
+///         ..Default::default()
+///     })
+///     .unwrap();
+///
+/// let client = pool.get().await.unwrap();
+/// let reply = client.transaction()
+///     .push("SELECT * FROM books LIMIT 1")
+///     .run()
+///     .await.unwrap();
+///
+/// // use a different database, just for this checkout
+/// let other = pool.get_with(|server| server.database = "other".to_string()).await.unwrap();
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct SurrealPool {
+    inner: Arc<PoolInner>,
+}
+
+pub(crate) type InterfaceFactory = Box<dyn Fn() -> Box<dyn SurrealInterface> + Send + Sync>;
+
+struct PoolInner {
+    builder: ClientBuilder,
+    server: ServerInfo,
+    config: PoolConfig,
+    interface: Option<InterfaceFactory>,
+    idle: Mutex<Vec<IdleClient>>,
+    permits: Arc<Semaphore>,
+}
+
+impl PoolInner {
+    fn connect(&self, server: ServerInfo) -> SdbResult<SurrealClient> {
+        match &self.interface {
+            Some( make ) => self.builder.client_with(server, make()),
+            None => self.builder.client_for(server),
+        }
+    }
+}
+
+struct IdleClient {
+    client: SurrealClient,
+    since: Instant,
+}
+
+impl SurrealPool {
+    pub(crate) fn new(
+        builder: ClientBuilder,
+        server: ServerInfo,
+        config: PoolConfig,
+        interface: Option<InterfaceFactory>,
+    ) -> SdbResult<Self> {
+        let max = config.max_connections.max(1);
+        let inner = PoolInner {
+            builder,
+            server,
+            interface,
+            idle: Mutex::new(Vec::with_capacity(max)),
+            permits: Arc::new(Semaphore::new(max)),
+            config,
+        };
+
+        for _ in 0..inner.config.min_connections.min(max) {
+            let client = inner.connect(inner.server.clone())?;
+            inner.idle.lock().unwrap().push(IdleClient {
+                client,
+                since: Instant::now(),
+            });
+        }
+
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// The connection settings every checkout starts with
+    pub fn server(&self) -> &ServerInfo {
+        &self.inner.server
+    }
+
+    /// How many connections are waiting to be checked out
+    pub fn idle(&self) -> usize {
+        self.inner.idle.lock().unwrap().len()
+    }
+
+    /// How many connections are checked out right now
+    pub fn in_use(&self) -> usize {
+        self.inner.config.max_connections.max(1) - self.inner.permits.available_permits()
+    }
+
+    /// Check out a client, waiting for one to be returned if all are in use, for
+    /// up to the pool's `checkout_timeout`
+    pub async fn get(&self) -> SdbResult<PooledClient> {
+        self.checkout(self.inner.server.clone()).await
+    }
+
+    /// Check out a client with its own namespace, database or credentials. These
+    /// only last until it is returned to the pool.
+    ///
+    /// Changing the host or protocol here has no effect.
+    pub async fn get_with(&self, configure: impl FnOnce(&mut ServerInfo)) -> SdbResult<PooledClient> {
+        let mut server = self.inner.server.clone();
+        configure(&mut server);
+        server.hostname = self.inner.server.hostname.clone();
+        server.protocol = self.inner.server.protocol.clone();
+        self.checkout(server).await
+    }
+
+    async fn checkout(&self, server: ServerInfo) -> SdbResult<PooledClient> {
+        let acquire = self.inner.permits.clone().acquire_owned();
+        let permit = match self.inner.config.checkout_timeout {
+            Some( waited ) => tokio::time::timeout(waited, acquire)
+                .await
+                .map_err(|_| SdbError::PoolTimeout { waited })?,
+            None => acquire.await,
+        }
+        .expect("The pool's semaphore is never closed");
+
+        while let Some(client) = self.take_idle() {
+            client.set_server(server.clone());
+            if self.inner.config.health_check && !client.is_healthy().await {
+                #[cfg(feature = "log")]
+                log::warn!("Dropping unhealthy pooled connection to {}", self.inner.server.full_url());
+                continue;
+            }
+
+            return Ok(PooledClient::new(client, self.clone(), permit));
+        }
+
+        let client = self.inner.connect(server)?;
+        Ok(PooledClient::new(client, self.clone(), permit))
+    }
+
+    /// Take the most recently used idle client, closing any which have timed out
+    fn take_idle(&self) -> Option<SurrealClient> {
+        let mut idle = self.inner.idle.lock().unwrap();
+
+        if let Some(timeout) = self.inner.config.idle_timeout {
+            let min = self.inner.config.min_connections;
+            let mut index = 0;
+            while idle.len() > min && index < idle.len() {
+                match idle[index].since.elapsed() > timeout {
+                    true => drop(idle.remove(index)),
+                    false => index += 1,
+                }
+            }
+        }
+
+        idle.pop().map(|idle| idle.client)
+    }
+
+    fn give_back(&self, client: SurrealClient) {
+        self.inner.idle.lock().unwrap().push(IdleClient {
+            client,
+            since: Instant::now(),
+        });
+    }
+}
+
+/// A [`SurrealClient`] checked out of a [`SurrealPool`]. It goes back to the pool
+/// when dropped.
+///
+/// [`SurrealClient`] is `Clone`, and cloning one through this guard makes a
+/// client which shares its connection but isn't counted by the pool. Keeping
+/// such clones around lets more requests run at once than `max_connections`
+pub struct PooledClient {
+    client: Option<SurrealClient>,
+    pool: SurrealPool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl PooledClient {
+    fn new(client: SurrealClient, pool: SurrealPool, permit: OwnedSemaphorePermit) -> Self {
+        Self {
+            client: Some(client),
+            pool,
+            _permit: permit,
+        }
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = SurrealClient;
+
+    fn deref(&self) -> &Self::Target {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.pool.give_back(client);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use ::tokio::time::{sleep, timeout};
+
+    use super::*;
+    use crate::prelude::*;
+
+    fn pool(config: PoolConfig) -> SurrealPool {
+        let mock = MockInterface::new();
+        SurrealClient::open("127.0.0.1:8000/test/demo")
+            .build_pool_with(config, move || mock.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn waits_for_a_free_connection() {
+        tokio_test::block_on(async {
+            let pool = pool(PoolConfig {
+                max_connections: 2,
+                ..Default::default()
+            });
+
+            let first = pool.get().await.unwrap();
+            let _second = pool.get().await.unwrap();
+            assert_eq!(pool.in_use(), 2);
+            assert!(timeout(Duration::from_millis(20), pool.get()).await.is_err());
+
+            drop(first);
+            let third = timeout(Duration::from_millis(20), pool.get()).await;
+            assert!(matches!(third, Ok(Ok(_))));
+        })
+    }
+
+    #[test]
+    fn checkout_times_out() {
+        tokio_test::block_on(async {
+            let pool = pool(PoolConfig {
+                max_connections: 1,
+                checkout_timeout: Some(Duration::from_millis(10)),
+                ..Default::default()
+            });
+
+            let _held = pool.get().await.unwrap();
+            let Err( err ) = pool.get().await else { panic!("Checked out more than max_connections") };
+            assert!(matches!(err, SdbError::PoolTimeout { .. }));
+        })
+    }
+
+    #[test]
+    fn clients_return_on_drop() {
+        tokio_test::block_on(async {
+            let pool = pool(PoolConfig::default());
+            assert_eq!((pool.idle(), pool.in_use()), (1, 0));
+
+            let client = pool.get().await.unwrap();
+            assert_eq!((pool.idle(), pool.in_use()), (0, 1));
+
+            drop(client);
+            assert_eq!((pool.idle(), pool.in_use()), (1, 0));
+        })
+    }
+
+    #[test]
+    fn idle_clients_are_pruned_on_checkout() {
+        tokio_test::block_on(async {
+            let pool = pool(PoolConfig {
+                min_connections: 1,
+                idle_timeout: Some(Duration::from_millis(10)),
+                ..Default::default()
+            });
+
+            let clients = vec![pool.get().await.unwrap(), pool.get().await.unwrap(), pool.get().await.unwrap()];
+            drop(clients);
+            assert_eq!(pool.idle(), 3);
+
+            sleep(Duration::from_millis(20)).await;
+            assert_eq!(pool.idle(), 3, "Pruning only happens on checkout");
+
+            let _client = pool.get().await.unwrap();
+            assert_eq!(pool.idle(), 0, "Keeps min_connections, then hands one out");
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use ::std::{fmt::Debug, sync::Arc, time::Duration};
+
+/// How a websocket client re-establishes its connection after it drops.
+///
+/// Each failed attempt waits `initial_delay * multiplier^(attempt - 1)`, capped at
+/// `max_delay`, before trying again. Requests that were waiting on a reply when the
+/// socket dropped still fail with [`SdbError::ConnectionClosed`](crate::prelude::SdbError),
+/// but any request made afterwards reconnects transparently.
+///
+/// ### Example
+/// ```rust
+/// # use sdb::prelude::*;
+/// # use std::time::Duration;
+/// let client = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo")
+///     .reconnect(ReconnectPolicy {
+///         max_attempts: Some(20),
+///         max_delay: Duration::from_secs(30),
+///         ..Default::default()
+///     })
+///     .on_reconnect(|event| println!("{event:?}"))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Give up after this many failed attempts. `None` retries forever
+    pub max_attempts: Option<u32>,
+    /// How long to wait after the first failed attempt
+    pub initial_delay: Duration,
+    /// The longest to ever wait between attempts
+    pub max_delay: Duration,
+    /// How much longer to wait after each failed attempt
+    pub multiplier: f64,
+}
+
+impl ReconnectPolicy {
+    /// Never try to reconnect. Every request after the socket drops fails
+    pub fn never() -> Self {
+        Self {
+            max_attempts: Some(0),
+            ..Default::default()
+        }
+    }
+
+    /// How long to wait after failed attempt number `attempt` (starting at 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        crate::retry::backoff(self.initial_delay, self.multiplier, self.max_delay, attempt)
+    }
+
+    /// Should another attempt be made after `attempt` failed ones
+    pub fn allows(&self, attempt: u32) -> bool {
+        match self.max_attempts {
+            Some(max) => attempt < max,
+            None => true,
+        }
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Some(10),
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+        }
+    }
+}
+
+/// Something that happened while re-establishing a dropped connection. See
+/// [`ClientBuilder::on_reconnect`](crate::prelude::ClientBuilder::on_reconnect)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReconnectEvent {
+    /// The connection was lost, and attempt number `attempt` failed. The next
+    /// attempt happens after `delay`
+    Retrying {
+        url: String,
+        attempt: u32,
+        delay: Duration,
+        reason: String,
+    },
+    /// The connection is back. The session (credentials, namespace, database and
+    /// connection-wide params) is replayed before any other request is sent
+    Reconnected { url: String, attempts: u32 },
+    /// The [`ReconnectPolicy`] ran out of attempts
+    GaveUp {
+        url: String,
+        attempts: u32,
+        reason: String,
+    },
+}
+
+/// Called with every [`ReconnectEvent`]
+pub type ReconnectListener = Arc<dyn Fn(&ReconnectEvent) + Send + Sync>;
+
+/// Passes an event to the listener, if there is one, and the log
+#[cfg(feature = "ws")]
+pub(crate) fn emit(listener: &Option<ReconnectListener>, event: ReconnectEvent) {
+    #[cfg(feature = "log")]
+    match &event {
+        ReconnectEvent::Retrying { .. } => log::warn!("{event:?}"),
+        ReconnectEvent::Reconnected { .. } => log::info!("{event:?}"),
+        ReconnectEvent::GaveUp { .. } => log::error!("{event:?}"),
+    }
+
+    if let Some(listener) = listener {
+        listener(&event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn attempts_allowed() {
+        assert!(!ReconnectPolicy::never().allows(0));
+
+        let policy = ReconnectPolicy { max_attempts: Some(2), ..Default::default() };
+        assert!(policy.allows(1));
+        assert!(!policy.allows(2));
+
+        let forever = ReconnectPolicy { max_attempts: None, ..Default::default() };
+        assert!(forever.allows(u32::MAX));
+    }
+
+    #[test]
+    fn delays_back_off() {
+        let policy = ReconnectPolicy::default();
+        assert_eq!(policy.delay(1), policy.initial_delay);
+        assert_eq!(policy.delay(2), policy.initial_delay * 2);
+        assert_eq!(policy.delay(100), policy.max_delay);
+    }
+}
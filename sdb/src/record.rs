@@ -4,10 +4,14 @@ use serde::{Serialize, Deserialize};
 mod any;
 mod id;
 mod link;
+mod patch;
+mod resource;
 
 pub use any::*;
 pub use id::*;
 pub use link::*;
+pub use patch::*;
+pub use resource::*;
 
 /// Anything which acts as a record in SurrealDB. Records can contain 
 /// links to other record types, which can be refer
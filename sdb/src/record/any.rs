@@ -97,10 +97,18 @@ impl<'de> Deserialize<'de> for AnyRecord {
     where
         D: Deserializer<'de>,
     {
+        use serde::de::Error;
+
         let parsed = <Value as Deserialize>::deserialize(deserializer)?;
-        let Value::Object( ref obj ) = parsed else { panic!() };
-        let Some( Value::String( id_str ) ) = obj.get("id") else { panic!() };
-        let id =RecordId::parse( id_str.clone() ).expect("A valid recordId");
+        let Value::Object( ref obj ) = parsed else {
+            return Err(D::Error::custom(format!("Expected a record, found {parsed}")))
+        };
+        let Some( Value::String( id_str ) ) = obj.get("id") else {
+            return Err(D::Error::missing_field("id"))
+        };
+        let Ok( id ) = RecordId::parse( id_str.clone() ) else {
+            return Err(D::Error::custom(format!("{id_str:?} is not a valid RecordId")))
+        };
         Ok( Self {
             id,
             fields: parsed
@@ -118,14 +126,9 @@ impl SurrealRecord for AnyRecord {
     }
 
     fn record_fields(&self) -> Map<String, Value> {
-        let Value::Object( map ) = &self.fields else { panic!() };
-        map.iter()
-            .filter_map(|(key, val)|{
-                match key.eq("id") {
-                    true => Some( (key.clone(), val.clone()) ),
-                    false => None,
-                }
-            })
-            .collect::<Map<String, Value>>()
+        match &self.fields {
+            Value::Object( map ) => map.clone(),
+            _ => Map::new(),
+        }
     }
 }
@@ -21,16 +21,17 @@ impl<'de, T: SurrealRecord> Deserialize<'de> for RecordLink<T> {
     where
         D: serde::Deserializer<'de>,
     {
+        use serde::de::Error;
+
         let val = Value::deserialize(deserializer)?;
-        let val_str = format!("{val:?}");
         match &val {
-            Value::String(s) => Ok(Self::Link(RecordId::parse(s).unwrap())),
+            Value::String(s) => match RecordId::parse(s) {
+                Ok(id) => Ok(Self::Link(id)),
+                Err(_) => Err(D::Error::custom(format!("{s:?} is not a valid RecordId"))),
+            },
             _ => match serde_json::from_value(val) {
                 Ok(thing) => Ok(Self::Record(Box::new(thing))),
-                Err(err) => {
-
-                    panic!("\n{val_str}\n{err:?}")
-                }
+                Err(err) => Err(D::Error::custom(err)),
             },
         }
     }
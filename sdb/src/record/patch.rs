@@ -0,0 +1,30 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// A single [JSON Patch](https://jsonpatch.com/) operation, for use with
+/// [`SurrealClient::patch`](crate::prelude::SurrealClient::patch). Paths look
+/// like `/title` or `/tags/0`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+impl PatchOp {
+    pub fn add(path: impl ToString, value: impl Into<Value>) -> Self {
+        Self::Add { path: path.to_string(), value: value.into() }
+    }
+
+    pub fn remove(path: impl ToString) -> Self {
+        Self::Remove { path: path.to_string() }
+    }
+
+    pub fn replace(path: impl ToString, value: impl Into<Value>) -> Self {
+        Self::Replace { path: path.to_string(), value: value.into() }
+    }
+}
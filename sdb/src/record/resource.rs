@@ -0,0 +1,74 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::RecordId;
+
+/// What a [`select`](crate::prelude::SurrealClient::select),
+/// [`delete`](crate::prelude::SurrealClient::delete), etc. acts on: either every
+/// record in a table, or a single record.
+///
+/// Strings containing a `:` are treated as record ids, everything else is a table.
+///
+/// ### Example
+/// ```rust
+/// # use sdb::prelude::*;
+/// assert_eq!(Resource::from("books"), Resource::Table("books".to_string()));
+/// assert_eq!(Resource::from("books:dune"), Resource::Record(RecordId::new("books", "dune")));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Resource {
+    Table(String),
+    Record(RecordId),
+}
+
+impl Resource {
+    pub fn table(&self) -> String {
+        match self {
+            Self::Table(table) => table.clone(),
+            Self::Record(id) => id.table(),
+        }
+    }
+
+    /// The record's key, if this is a single record
+    pub fn key(&self) -> Option<String> {
+        match self {
+            Self::Table(_) => None,
+            Self::Record(id) => Some(id.key()),
+        }
+    }
+}
+
+impl Display for Resource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Table(table) => write!(f, "{table}"),
+            Self::Record(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+impl From<&str> for Resource {
+    fn from(value: &str) -> Self {
+        match RecordId::parse(value) {
+            Ok(id) => Self::Record(id),
+            Err(_) => Self::Table(value.to_string()),
+        }
+    }
+}
+
+impl From<String> for Resource {
+    fn from(value: String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl From<RecordId> for Resource {
+    fn from(value: RecordId) -> Self {
+        Self::Record(value)
+    }
+}
+
+impl From<&RecordId> for Resource {
+    fn from(value: &RecordId) -> Self {
+        Self::Record(value.clone())
+    }
+}
@@ -1,5 +1,9 @@
+mod parse;
 mod statement;
+mod stats;
 mod transaction;
 
+pub use parse::*;
 pub use statement::*;
+pub use stats::*;
 pub use transaction::*;
\ No newline at end of file
@@ -0,0 +1,118 @@
+use ::serde::de::DeserializeOwned;
+use ::serde_json::Value;
+
+use crate::{error::*, record::{RecordId, SurrealRecord}};
+
+use super::{QueryReply, StatementResult};
+
+/// A type one statement's result can be parsed as. `Vec<T>` for any number of
+/// results, `Option<T>` for zero or one, and anything else for exactly one.
+///
+/// Implemented for records, primitives, `String`, [`Value`] and [`RecordId`].
+///
+/// Other `Deserialize` types, which aren't [`SurrealRecord`]s, can be parsed as
+/// `Vec<T>` or `Option<T>`, but not as exactly one result. Implementing this
+/// for every `DeserializeOwned` type would overlap with `Vec<T>` and `Option<T>`,
+/// which are deserializable too. Implement it yourself instead, with
+/// [`parse_one`](StatementResult::parse_one):
+///
+/// ```rust
+/// # use sdb::prelude::*;
+/// #[derive(serde::Deserialize)]
+/// struct Count {
+///     count: usize,
+/// }
+///
+/// impl FromStatement for Count {
+///     fn from_statement(result: &mut StatementResult) -> SdbResult<Self> {
+///         result.parse_one()
+///     }
+/// }
+///
+/// let mut result = StatementResult::ok(serde_json::json!([{ "count": 3 }]));
+/// assert_eq!(Count::from_statement(&mut result)?.count, 3);
+/// # Ok::<(), SdbError>(())
+/// ```
+///
+/// Without that, it fails to compile:
+///
+/// ```rust,compile_fail
+/// # use sdb::prelude::*;
+/// #[derive(serde::Deserialize)]
+/// struct Count {
+///     count: usize,
+/// }
+///
+/// let mut result = StatementResult::ok(serde_json::json!([{ "count": 3 }]));
+/// let count = Count::from_statement(&mut result);
+/// ```
+pub trait FromStatement: Sized {
+    fn from_statement(result: &mut StatementResult) -> SdbResult<Self>;
+}
+
+impl<T: DeserializeOwned> FromStatement for Vec<T> {
+    fn from_statement(result: &mut StatementResult) -> SdbResult<Self> {
+        result.parse_vec()
+    }
+}
+
+impl<T: DeserializeOwned> FromStatement for Option<T> {
+    fn from_statement(result: &mut StatementResult) -> SdbResult<Self> {
+        result.parse_opt()
+    }
+}
+
+impl<T: SurrealRecord> FromStatement for T {
+    fn from_statement(result: &mut StatementResult) -> SdbResult<Self> {
+        result.parse_one()
+    }
+}
+
+macro_rules! from_statement_one {
+    ( $($ty:ty),+ ) => {
+        $(
+            impl FromStatement for $ty {
+                fn from_statement(result: &mut StatementResult) -> SdbResult<Self> {
+                    result.parse_one()
+                }
+            }
+        )+
+    };
+}
+
+from_statement_one!(
+    bool, char, String, Value, RecordId,
+    i8, i16, i32, i64, i128, isize,
+    u8, u16, u32, u64, u128, usize,
+    f32, f64
+);
+
+/// A tuple of [`FromStatement`]s, one for each statement in a transaction
+/// which isn't skipped. See [`QueryReply::parse`]
+pub trait FromReply: Sized {
+    /// How many statements this is parsed from
+    const COUNT: usize;
+
+    fn from_reply(reply: &mut QueryReply) -> SdbResult<Self>;
+}
+
+macro_rules! from_reply_tuple {
+    ( $count:literal => $($name:ident),+ ) => {
+        impl<$($name: FromStatement),+> FromReply for ($($name,)+) {
+            const COUNT: usize = $count;
+
+            fn from_reply(reply: &mut QueryReply) -> SdbResult<Self> {
+                Ok(( $( $name::from_statement(reply.next_result()?)?, )+ ))
+            }
+        }
+    };
+}
+
+from_reply_tuple!(1 => A);
+from_reply_tuple!(2 => A, B);
+from_reply_tuple!(3 => A, B, C);
+from_reply_tuple!(4 => A, B, C, D);
+from_reply_tuple!(5 => A, B, C, D, E);
+from_reply_tuple!(6 => A, B, C, D, E, F);
+from_reply_tuple!(7 => A, B, C, D, E, F, G);
+from_reply_tuple!(8 => A, B, C, D, E, F, G, H);
@@ -7,48 +7,120 @@ use ::serde::{
 
 use crate::prelude::{SdbError, SdbResult};
 
-#[derive(Debug, Serialize)]
+/// Whether the server managed to run a statement
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatementStatus {
+    #[serde(rename = "OK")]
+    Ok,
+    #[serde(rename = "ERR")]
+    Err,
+    /// Something this version of `sdb` doesn't recognise
+    #[serde(other)]
+    Unknown,
+}
+
+impl StatementStatus {
+    pub fn is_ok(&self) -> bool {
+        *self == Self::Ok
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct StatementResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub query: Option<String>,
+    #[serde(serialize_with = "serialize_time")]
     pub time: Duration,
-    pub status: String,
+    pub status: StatementStatus,
     pub result: Value,
+    /// Why the statement failed, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Position of the statement in its transaction
+    #[serde(skip)]
+    pub(crate) index: usize,
 }
 
 impl StatementResult {
+    /// A statement which ran, and returned `result`. For scripting a
+    /// [`MockInterface`](crate::prelude::MockInterface)
+    pub fn ok(result: impl Into<Value>) -> Self {
+        Self {
+            query: None,
+            time: Duration::ZERO,
+            status: StatementStatus::Ok,
+            result: result.into(),
+            detail: None,
+            index: 0,
+        }
+    }
+
+    /// A statement which failed because of `detail`
+    pub fn err(detail: impl ToString) -> Self {
+        Self {
+            query: None,
+            time: Duration::ZERO,
+            status: StatementStatus::Err,
+            result: Value::Null,
+            detail: Some(detail.to_string()),
+            index: 0,
+        }
+    }
+
+    /// Returns [`SdbError::StatementFailed`] if the server couldn't run this statement
+    pub fn check(&self) -> SdbResult<()> {
+        if self.status.is_ok() {
+            return Ok(());
+        }
+
+        let detail = self.detail();
+        if detail.contains("exceeded the timeout") {
+            return Err(SdbError::QueryTimeout);
+        }
+
+        Err(SdbError::StatementFailed {
+            index: self.index,
+            sql: self.query(),
+            detail,
+        })
+    }
+
     pub fn parse_vec<T: for<'de> Deserialize<'de>>(&mut self) -> SdbResult<Vec<T>> {
+        self.check()?;
         match serde_json::from_value(self.result.clone()) {
             Ok(v) => Ok(v),
-            Err(err) => Err(SdbError::parse_failure::<T>(&self, err)),
+            Err(err) => Err(SdbError::parse_failure::<T>(self, err)),
         }
     }
 
     pub fn parse_one<T: for<'de> Deserialize<'de>>(&mut self) -> SdbResult<T> {
+        self.check()?;
         match &mut self.result {
-            Value::Array(arr) if arr.len() > 0 => {
+            Value::Array(arr) if !arr.is_empty() => {
                 match from_value::<T>(arr[0].clone()) {
                     Ok(v) => Ok(v),
-                    Err(err) => Err(SdbError::parse_failure::<T>(&self, err)),
+                    Err(err) => Err(SdbError::parse_failure::<T>(self, err)),
                 }
             },
             _ => {
                 match from_value::<T>(self.result.clone()) {
                     Ok(v) => Ok(v),
-                    Err(err) => Err(SdbError::parse_failure::<T>(&self, err)),
+                    Err(err) => Err(SdbError::parse_failure::<T>(self, err)),
                 }
             },
         }
     }
 
     pub fn parse_opt<T: for<'de> Deserialize<'de>>(&mut self) -> SdbResult<Option<T>> {
+        self.check()?;
         match &mut self.result {
-            Value::Array(arr) if arr.len() == 0 => {
+            Value::Array(arr) if arr.is_empty() => {
                 Ok(None)
             },
             Value::Array(arr) => {
                 match from_value::<T>(arr[0].clone()) {
                     Ok(v) => Ok(Some(v)),
-                    Err(err) => Err(SdbError::parse_failure::<T>(&self, err)),
+                    Err(err) => Err(SdbError::parse_failure::<T>(self, err)),
                 }
             },
             _ => {
@@ -58,7 +130,23 @@ impl StatementResult {
     }
 
     pub fn query(&self) -> String {
-        self.query.as_ref().unwrap().clone()
+        self.query.clone().unwrap_or_default()
+    }
+
+    /// Why the statement failed, or its status if the server didn't say
+    pub(crate) fn detail(&self) -> String {
+        match (&self.detail, &self.result) {
+            (Some(detail), _) => detail.clone(),
+            (None, Value::String(msg)) => msg.clone(),
+            (None, Value::Null) => format!("Statement status was {:?}", self.status),
+            (None, other) => other.to_string(),
+        }
+    }
+
+    /// Did this statement only fail because another in its transaction did
+    pub(crate) fn was_cancelled(&self) -> bool {
+        let detail = self.detail();
+        detail.contains("due to a failed transaction") || detail.contains("cancelled transaction")
     }
 }
 
@@ -97,13 +185,16 @@ impl<'de> Visitor<'de> for QueryResultVisitor {
                 }
                 "time" => {
                     let time_val: String = map.next_value()?;
-                    time = Some(parse_durration(time_val.as_str()));
+                    // how long it took isn't worth failing the whole reply over
+                    time = Some(parse_durration(time_val.as_str()).unwrap_or_default());
                 }
                 "detail" => {
                     detail = map.next_value()?;
                 }
                 _ => {
-                    println!(" Unknown {} => {:?}", k, map.next_value::<Value>()?);
+                    let _value = map.next_value::<Value>()?;
+                    #[cfg(feature = "log")]
+                    log::trace!("Ignoring unknown statement field {k:?}: {_value:?}");
                 }
             }
         }
@@ -115,32 +206,50 @@ impl<'de> Visitor<'de> for QueryResultVisitor {
                 time,
                 result,
                 status,
+                detail,
                 query: None,
+                index: 0,
             })
         } else if let Some(detail) = detail {
-            panic!("Query Failed: {detail}")
+            // A failed statement, which is only an error once someone reads it
+            Ok(StatementResult {
+                time: time.unwrap_or_default(),
+                result: Value::Null,
+                status: status.unwrap_or(StatementStatus::Err),
+                detail: Some(detail),
+                query: None,
+                index: 0,
+            })
         } else {
             Err(serde::de::Error::missing_field("detail"))
-            // panic!("TODO: implement ")
         }
     }
 }
 
-fn parse_durration(s: &str) -> Duration {
-    let float_str = &s[0..s.len() - 3];
-    let float = float_str.parse::<f64>().unwrap();
-
-    if s.ends_with("us") || s.ends_with("µs") {
-        Duration::from_secs_f64(float / 1_000_000.0)
-    } else if s.ends_with("ms") {
-        Duration::from_secs_f64(float / 1_000.0)
-    } else if s.ends_with('s') {
-        Duration::from_secs_f64(float)
-    } else if s.ends_with("ns") {
-        Duration::from_secs_f64(float / 1_000_000_000.0)
-    } else if s.ends_with("ps") {
-        Duration::from_secs_f64(float / 1_000_000_000_000.0)
-    } else {
-        panic!("Unrecognized duration suffix: {s}")
+/// Writes `time` the way the server does, so it can be read back
+fn serialize_time<S: serde::Serializer>(time: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{}s", time.as_secs_f64()))
+}
+
+/// Parses durations like `1.5ms` or `2m`. A number without a unit is in seconds
+pub(crate) fn parse_durration(s: &str) -> Option<Duration> {
+    let suffix_len = s.chars().rev().take_while(|c| c.is_alphabetic()).map(char::len_utf8).sum::<usize>();
+    let float = s[0..s.len() - suffix_len].parse::<f64>().ok()?;
+
+    let secs = match &s[s.len() - suffix_len..] {
+        "us" | "µs" => float / 1_000_000.0,
+        "ms" => float / 1_000.0,
+        "s" => float,
+        "ns" => float / 1_000_000_000.0,
+        "ps" => float / 1_000_000_000_000.0,
+        "" => float,
+        "m" => float * 60.0,
+        "h" => float * 3600.0,
+        _ => return None,
+    };
+
+    match secs.is_finite() && secs >= 0.0 {
+        true => Some(Duration::from_secs_f64(secs)),
+        false => None,
     }
 }
@@ -0,0 +1,87 @@
+use ::std::time::Duration;
+use ::serde_json::Value;
+
+use super::{StatementResult, StatementStatus};
+
+/// How long a transaction took, and how much it sent and received.
+/// See [`QueryReply::stats`](super::QueryReply::stats)
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryStats {
+    /// From sending the request to receiving the reply, network included
+    pub round_trip: Duration,
+    /// How long the server spent running the statements, added up
+    pub server_time: Duration,
+    /// Size of the SQL and variables sent, in bytes
+    pub request_bytes: usize,
+    /// Size of every statement's result as JSON, in bytes
+    pub response_bytes: usize,
+    /// How many rows every statement returned, added up
+    pub rows: usize,
+    /// Each statement, in order, including skipped ones
+    pub statements: Vec<StatementStats>,
+}
+
+/// How long one statement took the server to run, and how much it returned
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatementStats {
+    /// Position of the statement in its transaction
+    pub index: usize,
+    pub sql: String,
+    pub status: StatementStatus,
+    pub time: Duration,
+    /// How many rows it returned. A single value counts as one
+    pub rows: usize,
+    /// Size of its result as JSON, in bytes
+    pub bytes: usize,
+}
+
+impl StatementStats {
+    pub(crate) fn of(result: &StatementResult) -> Self {
+        let rows = match &result.result {
+            Value::Array(arr) => arr.len(),
+            Value::Null => 0,
+            _ => 1,
+        };
+
+        Self {
+            index: result.index,
+            sql: result.query(),
+            status: result.status,
+            time: result.time,
+            rows,
+            bytes: serde_json::to_vec(&result.result).map_or(0, |json| json.len()),
+        }
+    }
+}
+
+/// Measures how long something takes. Browsers don't have `Instant`, so wasm
+/// asks javascript for the time instead
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_family = "wasm"))]
+    start: ::std::time::Instant,
+    #[cfg(target_family = "wasm")]
+    start: f64,
+}
+
+#[cfg(not(target_family = "wasm"))]
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self { start: ::std::time::Instant::now() }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+#[cfg(target_family = "wasm")]
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self { start: js_sys::Date::now() }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        let millis = js_sys::Date::now() - self.start;
+        Duration::from_secs_f64(millis.max(0.0) / 1000.0)
+    }
+}
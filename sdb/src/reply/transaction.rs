@@ -1,8 +1,9 @@
+use ::std::time::Duration;
 use serde::Deserialize;
 
 use crate::{error::*, transaction::TransQuery};
 
-use super::StatementResult;
+use super::{FromReply, FromStatement, QueryStats, StatementResult, StatementStats, StatementStatus};
 
 /// The result of one entire SurrealDB transaction. Queries are grouped into
 /// transactions, even if you only use one.
@@ -10,40 +11,187 @@ pub struct QueryReply {
     pub(crate) index: usize,
     pub(crate) queries: Vec<TransQuery>,
     pub(crate) replies: Vec<StatementResult>,
+    /// How long the request took, set once the reply arrives
+    pub(crate) round_trip: Duration,
+    pub(crate) request_bytes: usize,
 }
 
 impl QueryReply {
     pub(crate) fn new(queries: Vec<TransQuery>, mut replies: Vec<StatementResult>) -> Self {
-        let mut idx = 0;
-        for q in queries.iter() {
-            replies.get_mut(idx).unwrap().query = Some(q.sql.clone());
-            idx += 1;
+        for (idx, reply) in replies.iter_mut().enumerate() {
+            reply.index = idx;
+            reply.query = queries.get(idx).map(|q| q.sql.clone());
         }
 
         Self {
             index: 0,
             queries,
             replies,
+            round_trip: Duration::ZERO,
+            request_bytes: 0,
         }
     }
 
-    pub fn next_result<'a>(&'a mut self) -> &'a mut StatementResult {
+    pub fn next_result(&mut self) -> SdbResult<&mut StatementResult> {
         while let Some( line ) = self.queries.get( self.index ) && line.skip {
             self.index += 1;
         }
 
-        let reply = self
-            .replies
-            .get_mut(self.index)
-            .expect("Too many calls to TransactionReply::next_*.");
+        let count = self.replies.len();
+        let Some( reply ) = self.replies.get_mut(self.index) else {
+            return Err(SdbError::StatementOutOfRange { index: self.index, count })
+        };
 
         #[cfg(feature = "log")]
         log::debug!("> {:?}\n", reply);
 
-        println!("> {reply:?}\n");
-
         self.index += 1;
-        reply
+        Ok(reply)
+    }
+
+    /// Parse the results of every statement left to read at once, as a tuple
+    /// with one [`FromStatement`](super::FromStatement) for each. Statements
+    /// which were skipped aren't counted
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let mut reply = client.transaction()
+    ///     .push("SELECT * FROM books LIMIT 3")
+    ///     .push("SELECT * FROM books WHERE title = 'Dune'")
+    ///     .push_skipped("LET $total = (SELECT count() FROM books GROUP ALL)")
+    ///     .push("RETURN $total.count")
+    ///     .run()
+    ///     .await?;
+    ///
+    /// let (books, dune, total) = reply.parse::<(Vec<Book>, Option<Book>, usize)>()?;
+    /// # });
+    /// ```
+    ///
+    /// Fails with [`SdbError::StatementCountMismatch`], without parsing anything,
+    /// if the tuple is a different length
+    pub fn parse<T: FromReply>(&mut self) -> SdbResult<T> {
+        let found = (self.index..self.replies.len())
+            .filter(|idx| !self.queries.get(*idx).is_some_and(|q| q.skip))
+            .count();
+
+        if found != T::COUNT {
+            return Err(SdbError::StatementCountMismatch { expected: T::COUNT, found })
+        }
+
+        T::from_reply(self)
+    }
+
+    /// Parse the result of the statement added with
+    /// [`push_named`](crate::prelude::TransactionBuilder::push_named), wherever
+    /// it is in the transaction. Doesn't move on what `next_*` reads
+    ///
+    /// Fails with [`SdbError::UnknownStatement`] if no statement has that name
+    pub fn get<T: FromStatement>(&mut self, name: &str) -> SdbResult<T> {
+        let position = self.queries.iter().position(|q| q.name.as_deref() == Some(name));
+        let Some( index ) = position else {
+            return Err(SdbError::UnknownStatement { name: name.to_string() })
+        };
+
+        let count = self.replies.len();
+        match self.replies.get_mut(index) {
+            Some( reply ) => T::from_statement(reply),
+            None => Err(SdbError::StatementOutOfRange { index, count }),
+        }
+    }
+
+    /// Parse the result of statement `index`, counting only those which aren't
+    /// skipped, like `next_*` does. Doesn't move on what `next_*` reads
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let mut reply = client.transaction()
+    ///     .push_skipped("LET $limit = 3")
+    ///     .push("SELECT * FROM books LIMIT $limit")
+    ///     .push("RETURN $limit")
+    ///     .run()
+    ///     .await?;
+    ///
+    /// let limit: usize = reply.at(1)?;
+    /// let books: Vec<Book> = reply.at(0)?;
+    /// # });
+    /// ```
+    pub fn at<T: FromStatement>(&mut self, index: usize) -> SdbResult<T> {
+        let queries = &self.queries;
+        let mut unskipped = self
+            .replies
+            .iter_mut()
+            .enumerate()
+            .filter(|(idx, _)| !queries.get(*idx).is_some_and(|q| q.skip))
+            .map(|(_, reply)| reply)
+            .collect::<Vec<_>>();
+
+        let count = unskipped.len();
+        match unskipped.get_mut(index) {
+            Some( reply ) => T::from_statement(reply),
+            None => Err(SdbError::StatementOutOfRange { index, count }),
+        }
+    }
+
+    /// Every statement's result, in order, including skipped ones. Each has the
+    /// SQL which produced it, and how long it took to run
+    pub fn iter(&self) -> impl Iterator<Item = &StatementResult> {
+        self.replies.iter()
+    }
+
+    /// How long the transaction took, both on the server and with the network,
+    /// how large it was, and how many rows each statement returned
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let reply = client.transaction()
+    ///     .push("SELECT * FROM books LIMIT 5")
+    ///     .run()
+    ///     .await?;
+    ///
+    /// let stats = reply.stats();
+    /// assert!(stats.round_trip >= stats.server_time);
+    /// assert_eq!(stats.rows, stats.statements[0].rows);
+    /// # });
+    /// ```
+    pub fn stats(&self) -> QueryStats {
+        let statements = self.replies.iter().map(StatementStats::of).collect::<Vec<_>>();
+
+        QueryStats {
+            round_trip: self.round_trip,
+            server_time: statements.iter().map(|s| s.time).sum(),
+            request_bytes: self.request_bytes,
+            response_bytes: statements.iter().map(|s| s.bytes).sum(),
+            rows: statements.iter().map(|s| s.rows).sum(),
+            statements,
+        }
+    }
+
+    /// The status of every statement in the transaction, in order
+    pub fn statuses(&self) -> Vec<StatementStatus> {
+        self.replies.iter().map(|r| r.status).collect()
+    }
+
+    /// Returns the first [`SdbError::StatementFailed`], if any statement failed.
+    /// Useful for failing fast, instead of finding out from `next_*`
+    pub fn check_all(&self) -> SdbResult<()> {
+        self.replies.iter().try_for_each(StatementResult::check)
+    }
+
+    /// Returns [`SdbError::TransactionFailed`] if an atomic transaction was
+    /// cancelled, naming the statement which caused it
+    pub(crate) fn check_atomic(&self) -> SdbResult<()> {
+        let failed = self.replies.iter().filter(|r| !r.status.is_ok()).collect::<Vec<_>>();
+        // the rest fail just because it did
+        let cause = failed.iter().find(|r| !r.was_cancelled()).or(failed.first());
+
+        match cause {
+            Some( cause ) => Err(SdbError::TransactionFailed {
+                index: cause.index,
+                sql: cause.query(),
+                detail: cause.detail(),
+            }),
+            None => Ok(()),
+        }
     }
 
     /// Get zero or more results
@@ -51,7 +199,7 @@ impl QueryReply {
     where
         T: for<'de> Deserialize<'de>,
     {
-        self.next_result().parse_vec()
+        self.next_result()?.parse_vec()
     }
 
     /// Get zero or one results
@@ -59,7 +207,7 @@ impl QueryReply {
     where
         T: for<'de> Deserialize<'de>,
     {
-        self.next_result().parse_opt()
+        self.next_result()?.parse_opt()
     }
 
     /// Get exactly one result, or an error
@@ -67,6 +215,135 @@ impl QueryReply {
     where
         T: for<'de> Deserialize<'de>,
     {
-        self.next_result().parse_one()
+        self.next_result()?.parse_one()
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod test {
+    use ::serde_json::{json, Value};
+
+    use super::*;
+    use crate::prelude::*;
+
+    /// Run the transaction `build` makes against a mock, which replies with `replies`
+    fn run(build: impl FnOnce(TransactionBuilder) -> TransactionBuilder, replies: Vec<StatementResult>) -> QueryReply {
+        let mock = MockInterface::new().expect_regex("(?s).*", replies);
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo").build_with(mock).unwrap();
+        tokio_test::block_on(build(client.transaction()).run()).unwrap()
+    }
+
+    fn books(trans: TransactionBuilder) -> TransactionBuilder {
+        trans
+            .push("SELECT * FROM books")
+            .push_skipped("LET $total = 2")
+            .push("RETURN $total")
+            .push("SELECT * FROM books:missing")
+    }
+
+    fn book_replies() -> Vec<StatementResult> {
+        vec![
+            StatementResult::ok(json!([{ "title": "Dune" }, { "title": "Emma" }])),
+            StatementResult::ok(Value::Null),
+            StatementResult::ok(2),
+            StatementResult::ok(json!([])),
+        ]
+    }
+
+    #[test]
+    fn parse_skips_skipped_statements() {
+        let mut reply = run(books, book_replies());
+        let (books, total, missing) = reply.parse::<(Vec<Value>, usize, Option<Value>)>().unwrap();
+        assert_eq!(books.len(), 2);
+        assert_eq!(total, 2);
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn parse_reads_what_is_left() {
+        let mut reply = run(books, book_replies());
+        reply.next_vec::<Value>().unwrap();
+        let (total, missing) = reply.parse::<(usize, Option<Value>)>().unwrap();
+        assert_eq!((total, missing), (2, None));
+    }
+
+    #[test]
+    fn parse_checks_the_count() {
+        let mut reply = run(books, book_replies());
+        let result = reply.parse::<(Vec<Value>, usize)>();
+        assert!(matches!(result, Err(SdbError::StatementCountMismatch { expected: 2, found: 3 })));
+
+        // nothing was read
+        assert_eq!(reply.next_vec::<Value>().unwrap().len(), 2);
+    }
+
+    fn named(trans: TransactionBuilder) -> TransactionBuilder {
+        trans
+            .push_named("books", "SELECT * FROM books")
+            .push_skipped("LET $total = 2")
+            .push_named("total", "RETURN $total")
+    }
+
+    fn named_replies() -> Vec<StatementResult> {
+        vec![
+            StatementResult::ok(json!([{ "title": "Dune" }])),
+            StatementResult::ok(Value::Null),
+            StatementResult::ok(2),
+        ]
+    }
+
+    #[test]
+    fn get_by_name() {
+        let mut reply = run(named, named_replies());
+        assert_eq!(reply.get::<usize>("total").unwrap(), 2);
+        assert_eq!(reply.get::<Vec<Value>>("books").unwrap().len(), 1);
+        assert!(matches!(reply.get::<usize>("authors"), Err(SdbError::UnknownStatement { .. })));
+
+        // doesn't move on next_*
+        assert_eq!(reply.next_vec::<Value>().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn at_counts_unskipped_statements() {
+        let mut reply = run(named, named_replies());
+        assert_eq!(reply.at::<usize>(1).unwrap(), 2);
+        assert_eq!(reply.at::<Vec<Value>>(0).unwrap().len(), 1);
+        assert!(matches!(reply.at::<usize>(2), Err(SdbError::StatementOutOfRange { index: 2, count: 2 })));
+
+        assert_eq!(reply.next_vec::<Value>().unwrap().len(), 1);
+        assert_eq!(reply.next_one::<usize>().unwrap(), 2);
+    }
+
+    #[test]
+    fn iter_includes_skipped_statements() {
+        let reply = run(named, named_replies());
+        let sqls = reply.iter().map(StatementResult::query).collect::<Vec<_>>();
+        assert_eq!(sqls, ["SELECT * FROM books", "LET $total = 2", "RETURN $total"]);
+    }
+
+    #[test]
+    fn stats_add_up_every_statement() {
+        let timed = |result: Value, millis| StatementResult {
+            time: Duration::from_millis(millis),
+            ..StatementResult::ok(result)
+        };
+        let reply = run(named, vec![
+            timed(json!([{ "title": "Dune" }, { "title": "Emma" }]), 3),
+            timed(Value::Null, 1),
+            timed(json!(2), 2),
+        ]);
+
+        let stats = reply.stats();
+        assert_eq!(stats.server_time, Duration::from_millis(6));
+        assert_eq!(stats.rows, 3);
+        let sql = "SELECT * FROM books;\n\tLET $total = 2;\n\tRETURN $total";
+        assert_eq!(stats.request_bytes, sql.len() + "{}".len());
+
+        let rows = stats.statements.iter().map(|s| s.rows).collect::<Vec<_>>();
+        assert_eq!(rows, [2, 0, 1]);
+        let bytes = stats.statements.iter().map(|s| s.bytes).collect::<Vec<_>>();
+        assert_eq!(bytes, [r#"[{"title":"Dune"},{"title":"Emma"}]"#.len(), "null".len(), "2".len()]);
+        assert_eq!(stats.response_bytes, bytes.iter().sum::<usize>());
+        assert_eq!(stats.statements[2].sql, "RETURN $total");
     }
 }
@@ -0,0 +1,312 @@
+use ::std::time::Duration;
+
+use crate::{error::SdbError, reply::QueryReply};
+
+/// How to re-run a transaction which failed in a way [`SdbError::is_retryable`](crate::prelude::SdbError::is_retryable)
+/// says might not happen again, like a conflict with another or a reset connection. Only [`atomic`](crate::prelude::TransactionBuilder::atomic)
+/// and [`idempotent`](crate::prelude::TransactionBuilder::idempotent) transactions
+/// are re-run, since others could be applied twice. Atomic ones are only re-run
+/// when they [weren't applied](crate::prelude::SdbError::was_not_applied), and
+/// not after a timeout or lost connection, unless they're idempotent too.
+///
+/// Each failed attempt waits `initial_delay * multiplier^(attempt - 1)`, capped at
+/// `max_delay`, and then changed by up to `jitter` of itself in either direction.
+///
+/// ### Example
+/// ```rust
+/// # use sdb::prelude::*;
+/// # use std::time::Duration;
+/// let client = SurrealClient::open("ws://demo_user:demo_pass@127.0.0.1:8000/example/demo")
+///     .retry(RetryPolicy {
+///         max_attempts: 5,
+///         ..Default::default()
+///     })
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// How many times to run a transaction, counting the first
+    pub max_attempts: u32,
+    /// How long to wait after the first failed attempt
+    pub initial_delay: Duration,
+    /// The longest to ever wait between attempts
+    pub max_delay: Duration,
+    /// How much longer to wait after each failed attempt
+    pub multiplier: f64,
+    /// How much each delay randomly differs, as a fraction of it. Keeps clients
+    /// which conflicted from retrying at the same moment and conflicting again
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Never re-run a transaction. The default for clients
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// How long to wait after failed attempt number `attempt` (starting at 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let secs = backoff(self.initial_delay, self.multiplier, self.max_delay, attempt).as_secs_f64();
+        let spread = 1.0 + self.jitter * (rand::random::<f64>() * 2.0 - 1.0);
+
+        match (secs * spread).is_finite() && secs * spread > 0.0 {
+            true => Duration::from_secs_f64(secs * spread),
+            false => Duration::ZERO,
+        }
+    }
+
+    /// Should another attempt be made after `attempt` failed ones
+    pub fn allows(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: 0.25,
+        }
+    }
+}
+
+/// `initial * multiplier^(attempt - 1)`, capped at `max`. How long both
+/// [`RetryPolicy`] and [`ReconnectPolicy`](crate::prelude::ReconnectPolicy)
+/// wait after failed attempt number `attempt` (starting at 1)
+pub(crate) fn backoff(initial: Duration, multiplier: f64, max: Duration, attempt: u32) -> Duration {
+    let exp = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+    let secs = initial.as_secs_f64() * multiplier.powi(exp);
+    match secs.is_finite() && secs < max.as_secs_f64() {
+        true => Duration::from_secs_f64(secs.max(0.0)),
+        false => max,
+    }
+}
+
+/// Did any statement in `reply` fail in a way `retryable` says is worth running
+/// it again for
+pub(crate) fn has_retryable_failure(reply: &QueryReply, retryable: impl Fn(&SdbError) -> bool) -> bool {
+    reply.replies.iter().any(|r| r.check().is_err_and(|err| retryable(&err)))
+}
+
+/// Wait without blocking the thread, or the browser
+pub(crate) async fn sleep(delay: Duration) {
+    #[cfg(all(feature = "tokio", not(target_family = "wasm")))]
+    tokio::time::sleep(delay).await;
+
+    #[cfg(all(not(feature = "tokio"), not(target_family = "wasm")))]
+    thread_sleep(delay).await;
+
+    #[cfg(target_family = "wasm")]
+    gloo_timers::future::sleep(delay).await;
+}
+
+/// Without tokio's timer, like with only a [`MockInterface`](crate::prelude::MockInterface),
+/// a thread waits instead and wakes the task when it's done
+#[cfg(all(not(feature = "tokio"), not(target_family = "wasm")))]
+async fn thread_sleep(delay: Duration) {
+    use ::std::{
+        sync::{Arc, Mutex},
+        task::{Poll, Waker},
+    };
+
+    // whether the delay is over, and the task to wake when it is
+    let state = Arc::new(Mutex::new((false, None::<Waker>)));
+    let timer_state = state.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        let mut state = timer_state.lock().unwrap();
+        state.0 = true;
+        if let Some( waker ) = state.1.take() {
+            waker.wake();
+        }
+    });
+
+    std::future::poll_fn(|cx| {
+        let mut state = state.lock().unwrap();
+        match state.0 {
+            true => Poll::Ready(()),
+            false => {
+                state.1 = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }).await
+}
+
+#[cfg(test)]
+mod test {
+    use ::std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+
+    use super::*;
+    use crate::prelude::*;
+
+    fn policy(jitter: f64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            multiplier: 2.0,
+            jitter,
+        }
+    }
+
+    #[test]
+    fn delays_grow_up_to_the_max() {
+        let policy = policy(0.0);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(300));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn jitter_stays_in_range() {
+        let policy = policy(0.5);
+        for _ in 0..100 {
+            let delay = policy.delay(1);
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(150), "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn backoff_grows_up_to_the_max() {
+        let ms = Duration::from_millis;
+        assert_eq!(backoff(ms(100), 2.0, ms(300), 0), ms(100));
+        assert_eq!(backoff(ms(100), 2.0, ms(300), 1), ms(100));
+        assert_eq!(backoff(ms(100), 2.0, ms(300), 2), ms(200));
+        assert_eq!(backoff(ms(100), 2.0, ms(300), 3), ms(300));
+        assert_eq!(backoff(ms(100), 2.0, ms(300), u32::MAX), ms(300));
+        assert_eq!(backoff(ms(100), 1.0, ms(300), 50), ms(100));
+    }
+
+    #[test]
+    fn backoff_survives_odd_multipliers() {
+        let ms = Duration::from_millis;
+        assert_eq!(backoff(ms(100), f64::INFINITY, ms(300), 2), ms(300));
+        assert_eq!(backoff(ms(100), f64::NAN, ms(300), 2), ms(300));
+        assert_eq!(backoff(Duration::ZERO, f64::INFINITY, ms(300), 2), ms(300));
+        assert_eq!(backoff(ms(100), -2.0, ms(300), 2), Duration::ZERO);
+    }
+
+    #[test]
+    fn attempts_allowed() {
+        assert!(!RetryPolicy::never().allows(1));
+        let policy = policy(0.0);
+        assert!(policy.allows(3));
+        assert!(!policy.allows(4));
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let failed = |detail: &str| SdbError::TransactionFailed {
+            index: 0,
+            sql: "CREATE books:1".to_string(),
+            detail: detail.to_string(),
+        };
+        assert!(failed("Failed to commit transaction due to a read or write conflict").is_retryable());
+        assert!(failed("Resource busy").is_retryable());
+        assert!(failed("This transaction can be retried").is_retryable());
+        assert!(!failed("Database record `books:1` already exists").is_retryable());
+        assert!(SdbError::QueryTimeout.is_retryable());
+        assert!(!SdbError::EmptyResponse { sql: String::new() }.is_retryable());
+
+        assert!(failed("Resource busy").was_not_applied());
+        assert!(SdbError::ConnectionRefused { url: String::new() }.was_not_applied());
+        assert!(!SdbError::NetworkTimeout.was_not_applied());
+        assert!(!SdbError::ConnectionClosed { info: String::new(), url: String::new() }.was_not_applied());
+    }
+
+    #[test]
+    fn sleeps() {
+        let delay = Duration::from_millis(20);
+        let start = std::time::Instant::now();
+        tokio_test::block_on(sleep(delay));
+        assert!(start.elapsed() >= delay);
+    }
+
+    #[cfg(feature = "mock")]
+    fn run_with_retries(replies: Vec<Vec<crate::reply::StatementResult>>, atomic: bool) -> crate::error::SdbResult<QueryReply> {
+        use crate::prelude::*;
+
+        let mock = replies.into_iter().fold(MockInterface::new(), |mock, replies| mock.expect("CREATE books:1", replies));
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo")
+            .retry(RetryPolicy { initial_delay: Duration::from_millis(1), ..Default::default() })
+            .build_with(mock)
+            .unwrap();
+
+        let trans = client.transaction().push("CREATE books:1");
+        let trans = match atomic {
+            true => trans.idempotent(),
+            false => trans,
+        };
+        tokio_test::block_on(trans.run())
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn retries_conflicts() {
+        use crate::reply::StatementResult;
+
+        let conflict = || vec![StatementResult::err("Transaction conflict: Resource busy")];
+        let mut reply = run_with_retries(vec![conflict(), conflict(), vec![StatementResult::ok(1)]], true).unwrap();
+        assert_eq!(reply.next_one::<u32>().unwrap(), 1);
+
+        // gives up after the policy's 3 attempts
+        let result = run_with_retries(vec![conflict(), conflict(), conflict(), vec![StatementResult::ok(1)]], true);
+        assert!(result.unwrap().next_one::<u32>().is_err());
+
+        // transactions which could apply twice run once
+        let result = run_with_retries(vec![conflict(), vec![StatementResult::ok(1)]], false);
+        assert!(result.unwrap().next_one::<u32>().is_err());
+    }
+
+    /// Fails every request with `err`, and counts them
+    struct Failing {
+        err: fn() -> SdbError,
+        sent: Arc<AtomicU32>,
+    }
+
+    #[cfg_attr(target_family = "wasm", async_trait::async_trait(?Send))]
+    #[cfg_attr(not(target_family = "wasm"), async_trait::async_trait)]
+    impl SurrealInterface for Failing {
+        async fn execute(&self, _server: &ServerInfo, _request: SurrealRequest) -> SdbResult<SurrealResponse> {
+            self.sent.fetch_add(1, Ordering::SeqCst);
+            Err((self.err)())
+        }
+    }
+
+    /// How many times a transaction is sent, when it always fails with `err`
+    fn times_sent(err: fn() -> SdbError, configure: fn(TransactionBuilder) -> TransactionBuilder) -> u32 {
+        let sent = Arc::new(AtomicU32::new(0));
+        let client = SurrealClient::open("127.0.0.1:8000/test/demo")
+            .retry(RetryPolicy { initial_delay: Duration::from_millis(1), ..Default::default() })
+            .build_with(Failing { err, sent: sent.clone() })
+            .unwrap();
+
+        let trans = configure(client.transaction().push("UPDATE books:1 SET reads += 1"));
+        assert!(tokio_test::block_on(trans.run()).is_err());
+        sent.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn atomic_transactions_arent_resent_after_timeouts() {
+        let timeout = || SdbError::NetworkTimeout;
+        let closed = || SdbError::ConnectionClosed { info: String::new(), url: String::new() };
+        let refused = || SdbError::ConnectionRefused { url: String::new() };
+
+        assert_eq!(times_sent(timeout, TransactionBuilder::atomic), 1);
+        assert_eq!(times_sent(closed, TransactionBuilder::atomic), 1);
+        assert_eq!(times_sent(refused, TransactionBuilder::atomic), 3);
+
+        assert_eq!(times_sent(timeout, TransactionBuilder::idempotent), 3);
+        assert_eq!(times_sent(timeout, |trans| trans.atomic().idempotent()), 3);
+        assert_eq!(times_sent(timeout, |trans| trans), 1);
+    }
+}
@@ -1,15 +1,31 @@
+use ::std::time::Duration;
 use ::serde::{Serialize, Deserialize};
+use ::serde_json::{Map, Value};
 
-use crate::{client::SurrealClient, error::*, reply::QueryReply};
+use crate::{client::SurrealClient, error::*, reply::QueryReply, retry::RetryPolicy};
 
+#[derive(Clone)]
 pub struct TransQuery {
     pub(crate) sql: String,
     pub(crate) skip: bool,
+    pub(crate) name: Option<String>,
 }
 
+/// The statements and variables to send in one request. It can be run any
+/// number of times, and cloned to run variations of it
+#[derive(Clone)]
 pub struct TransactionBuilder {
     client: SurrealClient,
     queries: Vec<TransQuery>,
+    vars: Map<String, Value>,
+    /// The name of the first variable which couldn't be serialized, and why.
+    /// Reported by `run`
+    var_error: Option<(String, String)>,
+    pub(crate) timeout: Option<Duration>,
+    timeout_clauses: bool,
+    pub(crate) atomic: bool,
+    pub(crate) idempotent: bool,
+    pub(crate) retry: Option<RetryPolicy>,
 }
 
 impl TransactionBuilder {
@@ -17,9 +33,95 @@ impl TransactionBuilder {
         Self {
             client: client.clone(),
             queries: Vec::new(),
+            vars: Map::new(),
+            var_error: None,
+            timeout: None,
+            timeout_clauses: false,
+            atomic: false,
+            idempotent: false,
+            retry: None,
         }
     }
 
+    /// Give up waiting for the reply after `timeout`, with [`SdbError::NetworkTimeout`],
+    /// instead of after the client's [default](crate::prelude::ClientBuilder::timeout)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Also add a `TIMEOUT` clause to every statement which can have one, so the
+    /// server stops working on them once the timeout passes too. Statements which
+    /// run out of time fail with [`SdbError::QueryTimeout`]
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// # use std::time::Duration;
+    /// let books = client.transaction()
+    ///     .push("SELECT * FROM books")
+    ///     .timeout(Duration::from_secs(2))
+    ///     .timeout_clauses(true)
+    ///     .run_parse_vec::<Book>()
+    ///     .await?;
+    /// # });
+    /// ```
+    pub fn timeout_clauses(mut self, enabled: bool) -> Self {
+        self.timeout_clauses = enabled;
+        self
+    }
+
+    /// Wrap the statements in `BEGIN TRANSACTION` and `COMMIT TRANSACTION`, so
+    /// either all of them are applied or none are. If any fails, running it
+    /// fails with [`SdbError::TransactionFailed`], naming the statement which did.
+    ///
+    /// The wrapping statements don't count towards statement indexes, or need
+    /// to be skipped with `next_*`
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let result = client.transaction()
+    ///     .push("CREATE books:atomic_demo SET title = 'Half Written'")
+    ///     // fails, because the record already exists
+    ///     .push("CREATE books:atomic_demo SET title = 'Half Written'")
+    ///     .atomic()
+    ///     .run()
+    ///     .await;
+    ///
+    /// assert!(matches!(result, Err(SdbError::TransactionFailed { index: 1, .. })));
+    /// # });
+    /// ```
+    pub fn atomic(mut self) -> Self {
+        self.atomic = true;
+        self
+    }
+
+    /// Promise that running this more than once does no harm, so a [`RetryPolicy`]
+    /// can re-run it after it times out or loses its connection, when it might
+    /// already have been applied. Even if it isn't [`atomic`](TransactionBuilder::atomic)
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    /// How to re-run this if it conflicts with another transaction, or loses its
+    /// connection, instead of the client's [policy](crate::prelude::ClientBuilder::retry).
+    /// Only applies to atomic or idempotent transactions
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let mut reply = client.transaction()
+    ///     .push("UPDATE books:dune SET reads += 1")
+    ///     .atomic()
+    ///     .retry(RetryPolicy::default())
+    ///     .run()
+    ///     .await?;
+    /// # });
+    /// ```
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
     /// Insert a new query into the transaction, which will produce a result
     /// when the transaction is run
     ///
@@ -50,12 +152,38 @@ impl TransactionBuilder {
         self.queries.push(TransQuery {
             sql: sql.to_string(),
             skip: false,
+            name: None,
         });
         self
     }
 
-    /// Inserts a value into the transaction. Queries pushed in after this can use
-    /// the variable. Inserting will sanitise the value as needed for its type.
+    /// Insert a new query which produces a result, and name it so its result
+    /// can be read with [`QueryReply::get`] wherever it ends up in the transaction
+    ///
+    /// ```rust
+    /// # sdb::doctest!( client => {
+    /// let mut reply = client.transaction()
+    ///     .push_named("books", "SELECT * FROM books LIMIT 5")
+    ///     .push_named("total", "RETURN count((SELECT * FROM books))")
+    ///     .run()
+    ///     .await?;
+    ///
+    /// let total: usize = reply.get("total")?;
+    /// let books: Vec<Book> = reply.get("books")?;
+    /// # });
+    /// ```
+    pub fn push_named(mut self, name: &str, sql: &str) -> Self {
+        self.queries.push(TransQuery {
+            sql: sql.to_string(),
+            skip: false,
+            name: Some(name.to_string()),
+        });
+        self
+    }
+
+    /// Binds a value to a variable, which every query in the transaction can use.
+    /// The value is sent alongside the SQL rather than inside it, so it never
+    /// needs escaping and doesn't add a statement to the transaction.
     ///
     /// Accepts anything which implements [`serde::Serialize`], which is all
     /// primitives, everything in [`std::collections`], and a lot more.
@@ -84,22 +212,23 @@ impl TransactionBuilder {
     /// # }
     /// ```
     pub fn push_var<T: Serialize>(mut self, var_name: &str, value: T) -> Self {
-        match serde_json::to_string(&value) {
-            Err(_e) => panic!("Cannot serialize value into variable `{var_name}`"),
-            Ok(val_string) => {
-                self.queries.push(TransQuery {
-                    sql: format!("LET ${var_name} = {val_string}"),
-                    skip: true,
-                });
-                self
+        match serde_json::to_value(&value) {
+            Ok(val) => {
+                self.vars.insert(var_name.to_string(), val);
+            }
+            Err(serde_err) => {
+                self.var_error.get_or_insert((var_name.to_string(), serde_err.to_string()));
             }
         }
+        self
     }
+
+    /// Bind an existing variable's value to another name. Used by the query macros
+    #[doc(hidden)]
     pub fn _name_var(mut self, new_name: &str, old_name: &str) -> Self {
-        self.queries.push(TransQuery {
-            sql: format!("LET ${new_name} = ${old_name}"),
-            skip: true,
-        });
+        if let Some(val) = self.vars.get(old_name).cloned() {
+            self.vars.insert(new_name.to_string(), val);
+        }
         self
     }
 
@@ -137,6 +266,7 @@ impl TransactionBuilder {
         self.queries.push(TransQuery {
             sql: sql.to_string(),
             skip: true,
+            name: None,
         });
         self
     }
@@ -177,29 +307,50 @@ impl TransactionBuilder {
         self.queries.push(TransQuery {
             sql: format!("LET ${var_name} = ({query})"),
             skip: true,
+            name: None,
         });
         self
     }
 
-    pub(crate) fn queries(self) -> (Vec<TransQuery>, Vec<String>) {
-        let sqls = self
+    /// The error for a variable which couldn't be serialized, if there was one
+    pub(crate) fn var_error(&self) -> Option<SdbError> {
+        let (name, reason) = self.var_error.as_ref()?;
+        Some(SdbError::InvalidVariable {
+            name: name.clone(),
+            serde_err: serde::ser::Error::custom(reason),
+        })
+    }
+
+    pub(crate) fn queries(mut self) -> (Vec<TransQuery>, Vec<String>, Map<String, Value>) {
+        let timeout = self.timeout.or(self.client.server().timeout);
+        if self.timeout_clauses && let Some( timeout ) = timeout {
+            for query in self.queries.iter_mut() {
+                query.sql = with_timeout_clause(&query.sql, timeout);
+            }
+        }
+
+        let mut sqls = self
             .queries
             .iter()
             .map(|q| q.sql.clone())
             .collect::<Vec<String>>();
 
-        (self.queries, sqls)
+        if self.atomic {
+            sqls.insert(0, "BEGIN TRANSACTION".to_string());
+            sqls.push("COMMIT TRANSACTION".to_string());
+        }
+
+        (self.queries, sqls, self.vars)
     }
 
     /// Executes the transaction and returns the results
-    pub async fn run(self) -> SdbResult<QueryReply> {
-        let mut client = self.client.clone();
-        client.query(self).await
+    pub async fn run(&self) -> SdbResult<QueryReply> {
+        self.client.query(self.clone()).await
     }
 
     /// Executes the transaction and then parses and returns a list of results from
     /// the first non-skipped query
-    pub async fn run_parse_vec<T>(self) -> SdbResult<Vec<T>>
+    pub async fn run_parse_vec<T>(&self) -> SdbResult<Vec<T>>
     where
         T: for<'de> Deserialize<'de>,
     {
@@ -209,7 +360,7 @@ impl TransactionBuilder {
 
     /// Executes the transaction and then parses and returns a single result from
     /// the first non-skipped query
-    pub async fn run_parse_opt<T>(self) -> SdbResult<Option<T>>
+    pub async fn run_parse_opt<T>(&self) -> SdbResult<Option<T>>
     where
         T: for<'de> Deserialize<'de>,
     {
@@ -219,7 +370,7 @@ impl TransactionBuilder {
 
     /// Executes the transaction and then parses and returns a non-optional single
     /// result from the first non-skipped query
-    pub async fn run_parse_one<T>(self) -> SdbResult<T>
+    pub async fn run_parse_one<T>(&self) -> SdbResult<T>
     where
         T: for<'de> Deserialize<'de>,
     {
@@ -227,3 +378,104 @@ impl TransactionBuilder {
         reply.next_one::<T>()
     }
 }
+
+/// Statements which can end with a `TIMEOUT` clause
+const TIMEOUT_STATEMENTS: [&str; 6] = ["SELECT", "CREATE", "UPDATE", "RELATE", "DELETE", "INSERT"];
+
+/// Add a `TIMEOUT` clause to a statement, if it can have one and doesn't already
+fn with_timeout_clause(sql: &str, timeout: Duration) -> String {
+    let trimmed = sql.trim().trim_end_matches(';');
+    let upper = trimmed.to_ascii_uppercase();
+    let first = upper.split(|c: char| !c.is_ascii_alphabetic()).next().unwrap_or_default();
+    if !TIMEOUT_STATEMENTS.contains(&first) || has_keyword(trimmed, "TIMEOUT") {
+        return sql.to_string();
+    }
+
+    let clause = format!("TIMEOUT {}ms", timeout.as_millis().max(1));
+    // PARALLEL has to come last
+    match upper.ends_with(" PARALLEL") {
+        true => {
+            let split = trimmed.len() - " PARALLEL".len();
+            format!("{} {clause}{}", &trimmed[..split], &trimmed[split..])
+        }
+        false => format!("{trimmed} {clause}"),
+    }
+}
+
+/// Is `keyword` one of the words of `sql`, outside of its strings and escaped
+/// identifiers
+fn has_keyword(sql: &str, keyword: &str) -> bool {
+    let mut quote = None;
+    let mut escaped = false;
+    let mut word = String::new();
+
+    for c in sql.chars().chain([' ']) {
+        match quote {
+            Some( _ ) if escaped => escaped = false,
+            Some( _ ) if c == '\\' => escaped = true,
+            Some( q ) if c == q => quote = None,
+            Some( _ ) => {}
+            None if c == '\'' || c == '"' || c == '`' => {
+                quote = Some(c);
+                word.clear();
+            }
+            None if c.is_ascii_alphanumeric() || c == '_' => word.push(c),
+            None => {
+                if word.eq_ignore_ascii_case(keyword) {
+                    return true;
+                }
+                word.clear();
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(250);
+
+    #[test]
+    fn adds_timeout_to_leading_keywords() {
+        assert_eq!(with_timeout_clause("SELECT * FROM books;", TIMEOUT), "SELECT * FROM books TIMEOUT 250ms");
+        assert_eq!(with_timeout_clause("  delete books:1", TIMEOUT), "delete books:1 TIMEOUT 250ms");
+        assert_eq!(
+            with_timeout_clause("SELECT * FROM books PARALLEL", TIMEOUT),
+            "SELECT * FROM books TIMEOUT 250ms PARALLEL",
+        );
+    }
+
+    #[test]
+    fn skips_keywords_which_dont_lead() {
+        for sql in [
+            "LET $books = (SELECT * FROM books)",
+            "RETURN 'SELECT * FROM books'",
+            "DEFINE TABLE books",
+        ] {
+            assert_eq!(with_timeout_clause(sql, TIMEOUT), sql);
+        }
+    }
+
+    #[test]
+    fn skips_existing_timeouts() {
+        let sql = "SELECT * FROM books TIMEOUT 5s";
+        assert_eq!(with_timeout_clause(sql, TIMEOUT), sql);
+
+        let sql = "select * from books timeout 5s;";
+        assert_eq!(with_timeout_clause(sql, TIMEOUT), sql);
+    }
+
+    #[test]
+    fn ignores_timeout_in_strings() {
+        assert_eq!(
+            with_timeout_clause("SELECT * FROM logs WHERE kind = 'TIMEOUT'", TIMEOUT),
+            "SELECT * FROM logs WHERE kind = 'TIMEOUT' TIMEOUT 250ms",
+        );
+        assert_eq!(
+            with_timeout_clause(r#"SELECT * FROM logs WHERE msg = "it's a \" TIMEOUT ""#, TIMEOUT),
+            r#"SELECT * FROM logs WHERE msg = "it's a \" TIMEOUT " TIMEOUT 250ms"#,
+        );
+    }
+}